@@ -60,6 +60,16 @@ fn _enum(
             #value => return Ok(Self::#variant_name),
         });
         choices.push(value);
+
+        // deprecated names are accepted but not advertised
+        for attr in &variant.attrs {
+            if attr.path().is_ident("cfgv_alias") {
+                let alias: syn::LitStr = attr.parse_args().unwrap();
+                m_code.push(quote! {
+                    #alias => return Ok(Self::#variant_name),
+                });
+            }
+        }
     }
 
     let msg = format!("Expected one of {} but got: {{}}", choices.join(", "));
@@ -169,7 +179,7 @@ fn _struct(
     }
 }
 
-#[proc_macro_derive(Cfgv, attributes(cfgv_id, cfgv_default, cfgv_default_expr))]
+#[proc_macro_derive(Cfgv, attributes(cfgv_alias, cfgv_id, cfgv_default, cfgv_default_expr))]
 pub fn cfgv(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = syn::parse_macro_input!(input as syn::DeriveInput);
 
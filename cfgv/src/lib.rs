@@ -1,5 +1,6 @@
 use serde::Deserialize;
 use serde_yaml::Value;
+use std::collections::HashMap;
 use std::fs;
 
 pub trait Cfgv {
@@ -85,20 +86,57 @@ impl<T: Cfgv> Cfgv for Vec<T> {
     }
 }
 
+impl<T: Cfgv> Cfgv for HashMap<String, T> {
+    fn cfgv_validate(ctx: &mut Vec<String>, v: &Value) -> anyhow::Result<Self> {
+        if let Value::Mapping(map) = v {
+            let mut ret: HashMap<String, T> = HashMap::new();
+
+            for (k, val) in map {
+                let k = String::cfgv_validate(ctx, k)?;
+                ctx.push(format!("At key: {k}"));
+                ret.insert(k, T::cfgv_validate(ctx, val)?);
+                ctx.pop();
+            }
+
+            Ok(ret)
+        } else {
+            anyhow::bail!(ctx_s(ctx, format!("Expected dict, got {}", type_name(v))))
+        }
+    }
+}
+
+/// any mapping, kept as-is
+impl Cfgv for serde_yaml::Mapping {
+    fn cfgv_validate(ctx: &mut Vec<String>, v: &Value) -> anyhow::Result<Self> {
+        if let Value::Mapping(map) = v {
+            Ok(map.clone())
+        } else {
+            anyhow::bail!(ctx_s(ctx, format!("Expected dict, got {}", type_name(v))))
+        }
+    }
+}
+
 pub fn parse<T: Cfgv>(v: &Value) -> anyhow::Result<T> {
     T::cfgv_validate(&mut Vec::new(), v)
 }
 
-pub fn load_file<T: Cfgv>(f: &str) -> anyhow::Result<T> {
-    let mut ctx = vec![format!("File {f}")];
+pub fn load_value(f: &str) -> anyhow::Result<Value> {
+    let ctx = vec![format!("File {f}")];
     let contents = match fs::read_to_string(f) {
         Ok(contents) => contents,
         Err(e) => anyhow::bail!(ctx_s(&ctx, e)),
     };
     let de = serde_yaml::Deserializer::from_str(&contents);
-    let value = match serde_yaml::Value::deserialize(de) {
-        Ok(value) => value,
+    match serde_yaml::Value::deserialize(de) {
+        Ok(value) => Ok(value),
         Err(e) => anyhow::bail!(ctx_s(&ctx, e)),
-    };
-    T::cfgv_validate(&mut ctx, &value)
+    }
+}
+
+pub fn validate_file<T: Cfgv>(f: &str, value: &Value) -> anyhow::Result<T> {
+    T::cfgv_validate(&mut vec![format!("File {f}")], value)
+}
+
+pub fn load_file<T: Cfgv>(f: &str) -> anyhow::Result<T> {
+    validate_file(f, &load_value(f)?)
 }
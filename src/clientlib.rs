@@ -1,13 +1,210 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::LazyLock;
+
+use clap::ValueEnum;
+
+use crate::identify;
+use crate::output;
+use crate::HookType;
 use crate::Stage;
 use cfgv::Cfgv;
 use cfgv_derive::Cfgv;
 use pre_commit_rs_derive::make_config_hook;
 
+/// a `files` / `exclude` pattern, compiled once when the config is loaded
+#[derive(Clone, Debug)]
+pub(crate) struct Regex(fancy_regex::Regex);
+
+impl Regex {
+    pub(crate) fn new(pattern: &str) -> anyhow::Result<Self> {
+        Ok(Regex(fancy_regex::Regex::new(pattern)?))
+    }
+
+    /// like python's `re.search`: the pattern may match anywhere
+    pub(crate) fn search(&self, s: &str) -> anyhow::Result<bool> {
+        Ok(self.0.is_match(s)?)
+    }
+}
+
+impl Default for Regex {
+    fn default() -> Self {
+        Regex::new("").unwrap()
+    }
+}
+
+impl Cfgv for Regex {
+    fn cfgv_validate(ctx: &mut Vec<String>, v: &serde_yaml::Value) -> anyhow::Result<Self> {
+        let pattern = String::cfgv_validate(ctx, v)?;
+        Regex::new(&pattern).map_err(|e| {
+            anyhow::anyhow!(cfgv::ctx_s(
+                ctx,
+                format!("'{pattern}' is not a valid python regex: {e}"),
+            ))
+        })
+    }
+}
+
+/// a file type tag (as produced by `identify`) used in `types`, `types_or`
+/// and `exclude_types`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct TypeTag(pub(crate) String);
+
+impl From<&str> for TypeTag {
+    fn from(s: &str) -> Self {
+        TypeTag(s.into())
+    }
+}
+
+impl Cfgv for TypeTag {
+    fn cfgv_validate(ctx: &mut Vec<String>, v: &serde_yaml::Value) -> anyhow::Result<Self> {
+        let tag = String::cfgv_validate(ctx, v)?;
+        if !identify::is_known_tag(&tag) {
+            anyhow::bail!(cfgv::ctx_s(
+                ctx,
+                format!("Type tag '{tag}' is not recognized.  Try upgrading pre-commit?"),
+            ));
+        }
+        Ok(TypeTag(tag))
+    }
+}
+
+static VERSION_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(
+        r"^v?(\d+(?:\.\d+)*)(?:[-_.]?(a|alpha|b|beta|c|rc|pre|preview)[-_.]?(\d*))?(?:[-_.]?(?:post|rev|r)[-_.]?(\d*)|-(\d+))?(?:[-_.]?dev[-_.]?(\d*))?$",
+    )
+    .unwrap()
+});
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct VersionKey {
+    release: Vec<u64>,
+    // `(0, 0)` for dev releases of a final version, then a < b < rc < final
+    pre: (u8, u64),
+    post: Option<u64>,
+    // `None` (not a dev release) sorts last
+    dev: (bool, u64),
+}
+
+/// a PEP 440-ish version: release segments with optional pre-release
+/// (`a` / `b` / `rc`), `.post` and `.dev` parts.  epochs and local versions
+/// are not supported
+#[derive(Clone, Debug)]
+pub(crate) struct Version {
+    s: String,
+    key: VersionKey,
+}
+
+impl Version {
+    pub(crate) fn parse(s: &str) -> Option<Self> {
+        let normalized = s.trim().to_lowercase();
+        let caps = VERSION_RE.captures(&normalized)?;
+        let num = |i: usize| caps.get(i).map(|m| m.as_str().parse().unwrap_or(0));
+
+        let mut release: Vec<u64> = caps[1]
+            .split('.')
+            .map(|part| part.parse().ok())
+            .collect::<Option<_>>()?;
+        // `1.0` == `1.0.0`
+        while release.len() > 1 && release.last() == Some(&0) {
+            release.pop();
+        }
+
+        let post = num(4).or(num(5));
+        let dev = num(6);
+        let pre = match caps.get(2).map(|m| m.as_str()) {
+            Some("a" | "alpha") => (1, num(3).unwrap_or(0)),
+            Some("b" | "beta") => (2, num(3).unwrap_or(0)),
+            Some(_) => (3, num(3).unwrap_or(0)),
+            None if post.is_none() && dev.is_some() => (0, 0),
+            None => (4, 0),
+        };
+
+        Some(Version {
+            s: s.into(),
+            key: VersionKey {
+                release,
+                pre,
+                post,
+                dev: (dev.is_none(), dev.unwrap_or(0)),
+            },
+        })
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.s)
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+/// `minimum_pre_commit_version`: validation fails when this binary is older
+#[derive(Clone, Debug)]
+pub(crate) struct MinimumVersion(pub(crate) Version);
+
+impl MinimumVersion {
+    fn check(&self) -> Result<(), String> {
+        let installed = env!("CARGO_PKG_VERSION");
+        if self.0 > Version::parse(installed).unwrap() {
+            Err(format!(
+                "pre-commit version {} is required but version {installed} is \
+                 installed.  Perhaps upgrade pre-commit?",
+                self.0,
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for MinimumVersion {
+    fn default() -> Self {
+        MinimumVersion(Version::parse("0").unwrap())
+    }
+}
+
+impl Cfgv for MinimumVersion {
+    fn cfgv_validate(ctx: &mut Vec<String>, v: &serde_yaml::Value) -> anyhow::Result<Self> {
+        let s = String::cfgv_validate(ctx, v)?;
+        let Some(version) = Version::parse(&s) else {
+            anyhow::bail!(cfgv::ctx_s(ctx, format!("'{s}' is not a valid version")));
+        };
+        let ret = MinimumVersion(version);
+        ret.check()
+            .map_err(|msg| anyhow::anyhow!(cfgv::ctx_s(ctx, msg)))?;
+        Ok(ret)
+    }
+}
+
 #[allow(dead_code)]
-#[derive(Cfgv, Debug)]
+#[derive(Cfgv, Clone, Debug)]
 pub(crate) struct ManifestHook {
     #[cfgv_id]
     pub(crate) id: String,
+    // checked first: a newer hook may use values we do not know about
+    #[cfgv_default]
+    pub(crate) minimum_pre_commit_version: MinimumVersion,
     pub(crate) name: String,
     pub(crate) entry: String,
     pub(crate) language: String,
@@ -15,15 +212,15 @@ pub(crate) struct ManifestHook {
     pub(crate) alias: String,
 
     #[cfgv_default]
-    pub(crate) files: String,
-    #[cfgv_default_expr("^$".into())]
-    pub(crate) exclude: String,
-    #[cfgv_default_expr(vec!["file".into()])]
-    pub(crate) types: Vec<String>,
+    pub(crate) files: Regex,
+    #[cfgv_default_expr(Regex::new("^$").unwrap())]
+    pub(crate) exclude: Regex,
+    #[cfgv_default_expr(vec![identify::FILE.into()])]
+    pub(crate) types: Vec<TypeTag>,
     #[cfgv_default]
-    pub(crate) types_or: Vec<String>,
+    pub(crate) types_or: Vec<TypeTag>,
     #[cfgv_default]
-    pub(crate) exclude_types: Vec<String>,
+    pub(crate) exclude_types: Vec<TypeTag>,
 
     #[cfgv_default]
     pub(crate) additional_dependencies: Vec<String>,
@@ -41,8 +238,6 @@ pub(crate) struct ManifestHook {
     pub(crate) language_version: String,
     #[cfgv_default]
     pub(crate) log_file: String,
-    #[cfgv_default_expr("0".into())]
-    pub(crate) minimum_pre_commit_version: String,
     #[cfgv_default]
     pub(crate) require_serial: bool,
     #[cfgv_default]
@@ -59,25 +254,25 @@ pub(crate) struct ConfigHook;
 #[derive(Cfgv, Debug)]
 pub(crate) struct LocalRepo {
     #[cfgv_id]
-    repo: String,
-    hooks: Vec<ManifestHook>,
+    pub(crate) repo: String,
+    pub(crate) hooks: Vec<ManifestHook>,
 }
 
 #[allow(dead_code)]
 #[derive(Cfgv, Debug)]
 pub(crate) struct MetaRepo {
     #[cfgv_id]
-    repo: String,
-    hooks: Vec<ConfigHook>,
+    pub(crate) repo: String,
+    pub(crate) hooks: Vec<ConfigHook>,
 }
 
 #[allow(dead_code)]
 #[derive(Cfgv, Debug)]
 pub(crate) struct RemoteRepo {
     #[cfgv_id]
-    repo: String,
-    rev: String,
-    hooks: Vec<ConfigHook>,
+    pub(crate) repo: String,
+    pub(crate) rev: String,
+    pub(crate) hooks: Vec<ConfigHook>,
 }
 
 #[allow(dead_code)]
@@ -104,32 +299,289 @@ impl Cfgv for Repo {
     }
 }
 
+#[derive(Cfgv, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum AutoupdateSchedule {
+    Weekly,
+    Monthly,
+    Quarterly,
+}
+
+const CI_KEYS: [&str; 7] = [
+    "autofix_commit_msg",
+    "autofix_prs",
+    "autoupdate_branch",
+    "autoupdate_commit_msg",
+    "autoupdate_schedule",
+    "skip",
+    "submodules",
+];
+
+/// the known keys of `ci:`, the settings for hosted runners (pre-commit.ci)
+#[allow(dead_code)]
+#[derive(Cfgv, Debug, Default)]
+pub(crate) struct CiSettings {
+    pub(crate) autofix_commit_msg: Option<String>,
+    pub(crate) autofix_prs: Option<bool>,
+    pub(crate) autoupdate_branch: Option<String>,
+    pub(crate) autoupdate_commit_msg: Option<String>,
+    pub(crate) autoupdate_schedule: Option<AutoupdateSchedule>,
+    #[cfgv_default]
+    pub(crate) skip: Vec<String>,
+    pub(crate) submodules: Option<bool>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Default)]
+pub(crate) struct Ci {
+    pub(crate) settings: CiSettings,
+    /// the mapping as written, including keys unknown to us
+    pub(crate) mapping: serde_yaml::Mapping,
+}
+
+impl Cfgv for Ci {
+    fn cfgv_validate(ctx: &mut Vec<String>, v: &serde_yaml::Value) -> anyhow::Result<Self> {
+        let mapping = serde_yaml::Mapping::cfgv_validate(ctx, v)?;
+        let settings = CiSettings::cfgv_validate(ctx, v)?;
+        Ok(Ci { settings, mapping })
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Cfgv, Debug)]
 pub(crate) struct Config {
-    repos: Vec<Repo>,
-
-    #[cfgv_default_expr(vec!["pre-commit".into()])]
-    default_install_hook_types: Vec<String>,
+    // checked first: a newer config may use values we do not know about
+    #[cfgv_default]
+    minimum_pre_commit_version: MinimumVersion,
+    pub(crate) repos: Vec<Repo>,
 
-    // TODO: idk what this should be
-    // default_language_version: ...,
+    #[cfgv_default_expr(vec![HookType::PreCommit])]
+    pub(crate) default_install_hook_types: Vec<HookType>,
 
-    // TODO: idk how to set the default nicely
-    // #[cfgv_default_expr=...]
-    // default_stages: Vec<String>>,
+    /// language => `language_version` for hooks which do not set one
     #[cfgv_default]
-    files: String,
-    #[cfgv_default_expr("^$".into())]
-    exclude: String,
+    pub(crate) default_language_version: HashMap<String, String>,
+    /// `stages` for hooks which do not set any
+    #[cfgv_default_expr(Stage::value_variants().to_vec())]
+    pub(crate) default_stages: Vec<Stage>,
     #[cfgv_default]
-    fail_fast: bool,
-    #[cfgv_default_expr("0".into())]
-    minimum_pre_commit_version: String,
-    // TODO: allow any mapping here
-    // ci: ...,
+    pub(crate) files: Regex,
+    #[cfgv_default_expr(Regex::new("^$").unwrap())]
+    pub(crate) exclude: Regex,
+    #[cfgv_default]
+    pub(crate) fail_fast: bool,
+    #[cfgv_default]
+    pub(crate) ci: Ci,
+}
+
+pub(crate) fn load_manifest(filename: &str) -> anyhow::Result<Vec<ManifestHook>> {
+    cfgv::load_file::<Vec<ManifestHook>>(filename)
+}
+
+const DEPRECATED_STAGES: [&str; 3] = ["commit", "merge-commit", "push"];
+
+fn _deprecated_stages(stages: Option<&serde_yaml::Value>) -> Vec<&str> {
+    let Some(serde_yaml::Value::Sequence(stages)) = stages else {
+        return Vec::new();
+    };
+    stages
+        .iter()
+        .filter_map(|stage| stage.as_str())
+        .filter(|stage| DEPRECATED_STAGES.contains(stage))
+        .collect()
+}
+
+fn _warn_for_deprecated_stages(v: &serde_yaml::Value) {
+    let repos = v.get("repos").and_then(|repos| repos.as_sequence());
+    for repo in repos.into_iter().flatten() {
+        let hooks = repo.get("hooks").and_then(|hooks| hooks.as_sequence());
+        for hook in hooks.into_iter().flatten() {
+            let deprecated = _deprecated_stages(hook.get("stages"));
+            if !deprecated.is_empty() {
+                let id = hook.get("id").and_then(|id| id.as_str()).unwrap_or("");
+                output::warning(format!(
+                    "hook id `{id}` uses deprecated stage names ({}) which will be \
+                     removed in a future version.  run: `pre-commit migrate-config` \
+                     to automatically fix this.",
+                    deprecated.join(", "),
+                ));
+            }
+        }
+    }
+
+    let deprecated = _deprecated_stages(v.get("default_stages"));
+    if !deprecated.is_empty() {
+        output::warning(format!(
+            "top-level `default_stages` uses deprecated stage names ({}) which \
+             will be removed in a future version.  run: \
+             `pre-commit migrate-config` to automatically fix this.",
+            deprecated.join(", "),
+        ));
+    }
+}
+
+fn _warn_unknown_ci_keys(v: &serde_yaml::Value) {
+    let Some(serde_yaml::Value::Mapping(ci)) = v.get("ci") else {
+        return;
+    };
+    let unknown: Vec<String> = ci
+        .keys()
+        .map(|k| k.as_str().map_or_else(|| format!("{k:?}"), String::from))
+        .filter(|k| !CI_KEYS.contains(&k.as_str()))
+        .collect();
+    if !unknown.is_empty() {
+        output::warning(format!(
+            "Unexpected key(s) present on ci: {}",
+            unknown.join(", ")
+        ));
+    }
 }
 
 pub(crate) fn load_config(filename: &str) -> anyhow::Result<Config> {
-    cfgv::load_file::<crate::clientlib::Config>(filename)
+    let value = cfgv::load_value(filename)?;
+    let config = cfgv::validate_file::<Config>(filename, &value)?;
+    _warn_for_deprecated_stages(&value);
+    _warn_unknown_ci_keys(&value);
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn _v(s: &str) -> Version {
+        Version::parse(s).unwrap()
+    }
+
+    #[test]
+    fn version_ordering() {
+        let ordered = [
+            "1.0.dev1",
+            "1.0a1",
+            "1.0b2",
+            "1.0rc1",
+            "1.0",
+            "1.0.post1",
+            "1.1",
+        ];
+        for pair in ordered.windows(2) {
+            assert!(_v(pair[0]) < _v(pair[1]), "{} < {}", pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn version_trailing_zeros() {
+        assert_eq!(_v("1.0"), _v("1.0.0"));
+        assert_eq!(_v("1"), _v("1.0.0"));
+        assert!(_v("1.0.0") < _v("1.0.1"));
+        assert!(_v("1.10") > _v("1.9"));
+    }
+
+    #[test]
+    fn version_prefixed() {
+        assert_eq!(_v("v1.2.3"), _v("1.2.3"));
+        assert_eq!(_v("V1.2.3").to_string(), "V1.2.3");
+    }
+
+    #[test]
+    fn version_rejected() {
+        for s in ["", "abc", "1.", ".1", "1..2", "1.0-foo", "1.0+local"] {
+            assert!(Version::parse(s).is_none(), "{s:?}");
+        }
+    }
+
+    #[test]
+    fn minimum_version() {
+        let installed = env!("CARGO_PKG_VERSION");
+        assert!(cfgv::parse::<MinimumVersion>(&installed.into()).is_ok());
+        assert!(cfgv::parse::<MinimumVersion>(&"0".into()).is_ok());
+
+        let err = cfgv::parse::<MinimumVersion>(&"999".into()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("pre-commit version 999 is required"));
+    }
+
+    #[test]
+    fn minimum_version_is_checked_first() {
+        let config = "\
+minimum_pre_commit_version: '999'
+repos:
+-   repo: local
+    hooks:
+    -   id: hook
+        name: hook
+        entry: echo
+        language: system
+        stages: [some-future-stage]
+";
+        let err = cfgv::parse::<Config>(&serde_yaml::from_str(config).unwrap()).unwrap_err();
+        assert!(err.to_string().contains("version 999 is required"), "{err}");
+
+        let manifest = "\
+-   id: hook
+    name: hook
+    entry: echo
+    language: system
+    types: [some-future-type]
+    minimum_pre_commit_version: '999'
+";
+        let err =
+            cfgv::parse::<Vec<ManifestHook>>(&serde_yaml::from_str(manifest).unwrap()).unwrap_err();
+        assert!(err.to_string().contains("version 999 is required"), "{err}");
+    }
+
+    #[test]
+    fn regex_search() {
+        // like python's `re.search`: not anchored unless asked to be
+        let re = Regex::new(r"\.py$").unwrap();
+        assert!(re.search("setup.py").unwrap());
+        assert!(re.search("a/b/c.py").unwrap());
+        assert!(!re.search("c.pyc").unwrap());
+
+        let re = Regex::new("^docs/").unwrap();
+        assert!(re.search("docs/index.md").unwrap());
+        assert!(!re.search("src/docs/index.md").unwrap());
+
+        assert!(Regex::new("foo").unwrap().search("a/foo/b").unwrap());
+        assert!(Regex::default().search("anything").unwrap());
+        assert!(!Regex::new("^$").unwrap().search("anything").unwrap());
+        // python-only syntax such as lookarounds is supported
+        let re = Regex::new(r"^(?!vendor/).*\.js$").unwrap();
+        assert!(re.search("src/x.js").unwrap());
+        assert!(!re.search("vendor/x.js").unwrap());
+    }
+
+    #[test]
+    fn regex_invalid() {
+        let config = "\
+files: '('
+repos: []
+";
+        let err = cfgv::parse::<Config>(&serde_yaml::from_str(config).unwrap()).unwrap_err();
+        let err = err.to_string();
+        assert!(err.contains("==> At key: files\n"), "{err}");
+        assert!(err.contains("'(' is not a valid python regex"), "{err}");
+
+        let config = "\
+repos:
+-   repo: local
+    hooks:
+    -   id: hook
+        name: hook
+        entry: echo
+        language: system
+        exclude: '['
+";
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = tmpdir.path().join(".pre-commit-config.yaml");
+        fs::write(&path, config).unwrap();
+        let path = path.to_string_lossy();
+        let err = load_config(&path).unwrap_err().to_string();
+        assert!(err.contains(&format!("==> File {path}\n")), "{err}");
+        assert!(err.contains("==> At ManifestHook(id=\"hook\")\n"), "{err}");
+        assert!(err.contains("==> At key: exclude\n"), "{err}");
+        assert!(err.contains("'[' is not a valid python regex"), "{err}");
+    }
 }
@@ -0,0 +1,32 @@
+use std::io;
+use std::io::IsTerminal;
+
+use crate::env_ext;
+use crate::ColorMode;
+
+pub(crate) const RED: &str = "\x1b[41m";
+pub(crate) const GREEN: &str = "\x1b[42m";
+pub(crate) const YELLOW: &str = "\x1b[43;30m";
+pub(crate) const TURQUOISE: &str = "\x1b[46;30m";
+pub(crate) const SUBTLE: &str = "\x1b[2m";
+pub(crate) const NORMAL: &str = "\x1b[m";
+
+pub(crate) fn format_color(text: &str, color: &str, use_color: bool) -> String {
+    if use_color {
+        format!("{color}{text}{NORMAL}")
+    } else {
+        text.into()
+    }
+}
+
+pub(crate) fn use_color(mode: ColorMode) -> bool {
+    match mode {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => {
+            io::stdout().is_terminal()
+                && env_ext::var_os_nonempty("NO_COLOR").is_none()
+                && env_ext::var_os_nonempty("TERM").is_none_or(|term| term != "dumb")
+        }
+    }
+}
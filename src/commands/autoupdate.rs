@@ -0,0 +1,396 @@
+use std::fs;
+use std::sync::{mpsc, LazyLock, Mutex};
+use std::thread;
+
+use regex::Regex;
+
+use crate::clientlib;
+use crate::clientlib::{RemoteRepo, Repo};
+use crate::commands::migrate_config;
+use crate::git;
+use crate::repository;
+use crate::Autoupdate;
+
+#[derive(Clone, Debug)]
+struct RevInfo {
+    rev: String,
+    frozen: Option<String>,
+}
+
+/// Multiple tags can exist on a SHA.  Sometimes a moving tag is attached
+/// to a version tag.  Try to pick the tag that looks like a version.
+fn _best_candidate_tag(rev: &str, tmp: &std::path::Path) -> anyhow::Result<String> {
+    let tags = git::cmd_output(tmp, &["tag", "--points-at", rev])?;
+    Ok(tags
+        .lines()
+        .find(|tag| tag.contains('.'))
+        .unwrap_or(rev)
+        .into())
+}
+
+fn _update(repo: &RemoteRepo, tags_only: bool, freeze: bool) -> anyhow::Result<RevInfo> {
+    let tmp = tempfile::tempdir()?;
+    let tmp = tmp.path();
+
+    git::init_repo(tmp, &repo.repo)?;
+    git::cmd_output(tmp, &["config", "extensions.partialClone", "true"])?;
+    git::cmd_output(
+        tmp,
+        &[
+            "fetch",
+            "origin",
+            "HEAD",
+            "--quiet",
+            "--filter=blob:none",
+            "--tags",
+        ],
+    )?;
+
+    let tag_opt = if tags_only { "--abbrev=0" } else { "--exact" };
+    let mut rev = match git::cmd_output(tmp, &["describe", "FETCH_HEAD", "--tags", tag_opt]) {
+        Ok(out) if tags_only => _best_candidate_tag(out.trim(), tmp)?,
+        Ok(out) => out.trim().into(),
+        Err(_) => git::cmd_output(tmp, &["rev-parse", "FETCH_HEAD"])?
+            .trim()
+            .into(),
+    };
+
+    let mut frozen = None;
+    if freeze {
+        let exact = git::cmd_output(tmp, &["rev-parse", &rev])?
+            .trim()
+            .to_string();
+        if exact != rev {
+            frozen = Some(std::mem::replace(&mut rev, exact));
+        }
+    }
+
+    // a missing manifest is reported by the manifest loading below
+    let _ = git::cmd_output(tmp, &["checkout", &rev, "--", repository::MANIFEST_FILE]);
+    let manifest_path = tmp.join(repository::MANIFEST_FILE);
+    let manifest = clientlib::load_manifest(&manifest_path.to_string_lossy())
+        .map_err(|e| anyhow::anyhow!("[{}] {e:#}", repo.repo))?;
+
+    let mut missing: Vec<&str> = repo
+        .hooks
+        .iter()
+        .map(|hook| hook.id.as_str())
+        .filter(|id| !manifest.iter().any(|hook| hook.id == *id))
+        .collect();
+    if !missing.is_empty() {
+        missing.sort();
+        missing.dedup();
+        anyhow::bail!(
+            "[{}] Cannot update because the update target is missing these hooks: {}",
+            repo.repo,
+            missing.join(", "),
+        );
+    }
+
+    Ok(RevInfo { rev, frozen })
+}
+
+static REV_LINE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"^(\s+)rev:(\s*)(['"]?)([^\s#]+)(.*?)(\r?\n)$"#).unwrap());
+
+fn _dump_rev(rev: &str, quote: &str) -> anyhow::Result<String> {
+    match quote {
+        "'" => Ok(format!("'{}'", rev.replace('\'', "''"))),
+        "\"" => Ok(format!(
+            "\"{}\"",
+            rev.replace('\\', "\\\\").replace('"', "\\\"")
+        )),
+        _ => Ok(serde_yaml::to_string(rev)?.trim_end().into()),
+    }
+}
+
+/// detect `rev:` lines or reformat the file
+fn _original_lines(path: &str, count: usize, retry: bool) -> anyhow::Result<Vec<String>> {
+    let original = fs::read_to_string(path)?;
+    let lines: Vec<String> = original.split_inclusive('\n').map(String::from).collect();
+    if lines.iter().filter(|l| REV_LINE_RE.is_match(l)).count() == count {
+        Ok(lines)
+    } else if retry {
+        anyhow::bail!("could not find rev lines in {path}");
+    } else {
+        let contents: serde_yaml::Value = serde_yaml::from_str(&original)?;
+        fs::write(path, serde_yaml::to_string(&contents)?)?;
+        _original_lines(path, count, true)
+    }
+}
+
+fn _write_new_config(path: &str, rev_infos: &[Option<RevInfo>]) -> anyhow::Result<()> {
+    let mut lines = _original_lines(path, rev_infos.len(), false)?;
+
+    let idxs: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| REV_LINE_RE.is_match(line))
+        .map(|(i, _)| i)
+        .collect();
+    for (idx, rev_info) in idxs.into_iter().zip(rev_infos) {
+        let Some(rev_info) = rev_info else {
+            continue;
+        };
+        let caps = REV_LINE_RE.captures(&lines[idx]).unwrap();
+        let new_rev = _dump_rev(&rev_info.rev, &caps[3])?;
+        let comment = if let Some(frozen) = &rev_info.frozen {
+            format!("  # frozen: {frozen}")
+        } else if caps[5].trim().starts_with("# frozen:") {
+            String::new()
+        } else {
+            caps[5].into()
+        };
+        lines[idx] = format!("{}rev:{}{new_rev}{comment}{}", &caps[1], &caps[2], &caps[6]);
+    }
+
+    fs::write(path, lines.concat())?;
+    Ok(())
+}
+
+pub(crate) fn cmd(config: &str, cmd: Autoupdate) -> anyhow::Result<i32> {
+    migrate_config::migrate_config(config, true)?;
+
+    let config_repos: Vec<RemoteRepo> = clientlib::load_config(config)?
+        .repos
+        .into_iter()
+        .filter_map(|repo| match repo {
+            Repo::Remote(repo) => Some(repo),
+            _ => None,
+        })
+        .collect();
+
+    let todo: Vec<(usize, &RemoteRepo)> = config_repos
+        .iter()
+        .enumerate()
+        .filter(|(_, repo)| cmd.repos.is_empty() || cmd.repos.contains(&repo.repo))
+        .collect();
+
+    let jobs = match cmd.jobs {
+        // 0 => number of cpus
+        0 => thread::available_parallelism().map_or(1, usize::from),
+        jobs => jobs as usize,
+    };
+    let jobs = jobs.min(todo.len()).max(1);
+
+    let tags_only = !cmd.bleeding_edge;
+    let mut results: Vec<Option<anyhow::Result<RevInfo>>> =
+        config_repos.iter().map(|_| None).collect();
+
+    let queue = Mutex::new(todo.into_iter());
+    let (tx, rx) = mpsc::channel();
+    thread::scope(|s| {
+        for _ in 0..jobs {
+            let tx = tx.clone();
+            let queue = &queue;
+            s.spawn(move || loop {
+                let Some((i, repo)) = queue.lock().unwrap().next() else {
+                    break;
+                };
+                let res = _update(repo, tags_only, cmd.freeze);
+                if tx.send((i, res)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(tx);
+
+        for (i, res) in rx {
+            results[i] = Some(res);
+        }
+    });
+
+    // report in config order regardless of which fetch finished first
+    let mut rev_infos: Vec<Option<RevInfo>> = vec![None; config_repos.len()];
+    let mut changed = false;
+    let mut retv = 0;
+    for (i, (old, res)) in config_repos.iter().zip(results).enumerate() {
+        match res {
+            None => {}
+            Some(Ok(new)) if new.rev != old.rev => {
+                changed = true;
+                let new_s = match &new.frozen {
+                    Some(frozen) => format!("{frozen} (frozen)"),
+                    None => new.rev.clone(),
+                };
+                println!("[{}] updating {} -> {new_s}", old.repo, old.rev);
+                rev_infos[i] = Some(new);
+            }
+            Some(Ok(_)) => println!("[{}] already up to date!", old.repo),
+            Some(Err(e)) => {
+                println!("{e:#}");
+                retv = 1;
+            }
+        }
+    }
+
+    if changed {
+        _write_new_config(config, &rev_infos)?;
+    }
+
+    Ok(retv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn _rev(rev: &str, frozen: Option<&str>) -> Option<RevInfo> {
+        Some(RevInfo {
+            rev: rev.into(),
+            frozen: frozen.map(String::from),
+        })
+    }
+
+    fn _rewrite(contents: &str, rev_infos: &[Option<RevInfo>]) -> String {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = tmpdir.path().join(".pre-commit-config.yaml");
+        let path = path.to_str().unwrap();
+        fs::write(path, contents).unwrap();
+        _write_new_config(path, rev_infos).unwrap();
+        fs::read_to_string(path).unwrap()
+    }
+
+    fn _remote_repo(repo: &str, hook_id: &str) -> RemoteRepo {
+        let contents = format!("repo: {repo}\nrev: v1.0\nhooks: [{{id: {hook_id}}}]\n");
+        cfgv::parse(&serde_yaml::from_str(&contents).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn write_new_config_plain() {
+        let contents = "repos:\n-   repo: r\n    rev: v1\n    hooks: []\n";
+        let ret = _rewrite(contents, &[_rev("v2", None)]);
+        assert_eq!(ret, "repos:\n-   repo: r\n    rev: v2\n    hooks: []\n");
+    }
+
+    #[test]
+    fn write_new_config_quoted() {
+        let contents = "\
+repos:
+-   repo: r1
+    rev: 'v1'
+    hooks: []
+-   repo: r2
+    rev: \"v1\"
+    hooks: []
+";
+        let ret = _rewrite(contents, &[_rev("v2", None), _rev("v3", None)]);
+        assert!(ret.contains("    rev: 'v2'\n"), "{ret}");
+        assert!(ret.contains("    rev: \"v3\"\n"), "{ret}");
+    }
+
+    #[test]
+    fn write_new_config_keeps_comments_and_skips_unchanged() {
+        let contents = "\
+repos:
+-   repo: r1
+    rev: v1  # pinned for reasons
+    hooks: []
+-   repo: r2
+    rev: v1
+    hooks: []
+";
+        let ret = _rewrite(contents, &[_rev("v2", None), None]);
+        assert_eq!(
+            ret,
+            "\
+repos:
+-   repo: r1
+    rev: v2  # pinned for reasons
+    hooks: []
+-   repo: r2
+    rev: v1
+    hooks: []
+",
+        );
+    }
+
+    #[test]
+    fn write_new_config_adds_frozen_comment() {
+        let contents = "repos:\n-   repo: r\n    rev: v1\n    hooks: []\n";
+        let ret = _rewrite(contents, &[_rev("abc123", Some("v2"))]);
+        assert!(ret.contains("    rev: abc123  # frozen: v2\n"), "{ret}");
+    }
+
+    #[test]
+    fn write_new_config_replaces_frozen_comment() {
+        let contents = "repos:\n-   repo: r\n    rev: abc123  # frozen: v1\n    hooks: []\n";
+        let ret = _rewrite(contents, &[_rev("def456", Some("v2"))]);
+        assert!(ret.contains("    rev: def456  # frozen: v2\n"), "{ret}");
+
+        let ret = _rewrite(contents, &[_rev("v2", None)]);
+        assert!(ret.contains("    rev: v2\n"), "{ret}");
+        assert!(!ret.contains("frozen"), "{ret}");
+    }
+
+    #[test]
+    fn write_new_config_reformats_when_rev_lines_mismatch() {
+        let contents = "repos: [{repo: r, rev: v1, hooks: []}]\n";
+        let ret = _rewrite(contents, &[_rev("v2", None)]);
+        assert!(ret.contains("rev: v2\n"), "{ret}");
+
+        let value: serde_yaml::Value = serde_yaml::from_str(&ret).unwrap();
+        assert_eq!(value["repos"][0]["repo"], "r");
+        assert_eq!(value["repos"][0]["rev"], "v2");
+    }
+
+    #[test]
+    fn update_to_tags_and_bleeding_edge() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let repo = testing::hooks_repo(tmpdir.path());
+        let remote = _remote_repo(&repo, "hook");
+
+        let ret = _update(&remote, true, false).unwrap();
+        assert_eq!(ret.rev, "v2.0");
+        assert_eq!(ret.frozen, None);
+
+        let head = git::cmd_output(&repo, &["rev-parse", "HEAD"]).unwrap();
+        let ret = _update(&remote, false, false).unwrap();
+        assert_eq!(ret.rev, head.trim());
+
+        let tagged = git::cmd_output(&repo, &["rev-parse", "v2.0^{commit}"]).unwrap();
+        let ret = _update(&remote, true, true).unwrap();
+        assert_eq!(ret.rev, tagged.trim());
+        assert_eq!(ret.frozen.as_deref(), Some("v2.0"));
+    }
+
+    #[test]
+    fn update_missing_hook() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let repo = testing::hooks_repo(tmpdir.path());
+
+        let err = _update(&_remote_repo(&repo, "gone"), true, false).unwrap_err();
+        assert!(
+            err.to_string().ends_with("is missing these hooks: gone"),
+            "{err}",
+        );
+    }
+
+    #[test]
+    fn autoupdate_end_to_end() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let repo = testing::hooks_repo(tmpdir.path());
+
+        let config = tmpdir.path().join(".pre-commit-config.yaml");
+        let config = config.to_str().unwrap();
+        let contents = format!(
+            "repos:\n-   repo: {repo}\n    rev: 'v1.0'  # keep me\n    hooks:\n    -   id: hook\n"
+        );
+        fs::write(config, contents).unwrap();
+
+        let cmd_args = Autoupdate {
+            bleeding_edge: false,
+            freeze: false,
+            repos: Vec::new(),
+            jobs: 1,
+        };
+        assert_eq!(cmd(config, cmd_args).unwrap(), 0);
+        assert_eq!(
+            fs::read_to_string(config).unwrap(),
+            format!(
+                "repos:\n-   repo: {repo}\n    rev: 'v2.0'  # keep me\n    hooks:\n    -   id: hook\n"
+            ),
+        );
+    }
+}
@@ -1,5 +1,5 @@
-pub(crate) fn cmd(store: crate::store::Store) -> anyhow::Result<()> {
+pub(crate) fn cmd(store: crate::store::Store) -> anyhow::Result<i32> {
     rm_rf::remove(&store.directory)?;
     println!("Cleaned {}.", store.directory.display());
-    Ok(())
+    Ok(0)
 }
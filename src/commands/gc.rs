@@ -0,0 +1,205 @@
+use std::collections::{HashMap, HashSet};
+use std::path;
+
+use crate::clientlib;
+use crate::clientlib::{ManifestHook, Repo};
+use crate::repository;
+use crate::store;
+
+type RepoKey = (String, String);
+
+fn _mark_used_repos(
+    all_repos: &HashMap<RepoKey, path::PathBuf>,
+    unused_repos: &mut HashSet<RepoKey>,
+    repo: &Repo,
+) {
+    match repo {
+        Repo::Meta(_) => {}
+        Repo::Local(repo) => {
+            for hook in &repo.hooks {
+                let name = store::Store::db_repo_name(&repo.repo, &hook.additional_dependencies);
+                unused_repos.remove(&(name, store::LOCAL_REPO_VERSION.into()));
+            }
+        }
+        Repo::Remote(repo) => {
+            let key = (repo.repo.clone(), repo.rev.clone());
+            // can't inspect manifest if it isn't cloned
+            let Some(path) = all_repos.get(&key) else {
+                return;
+            };
+            let manifest_path = path.join(repository::MANIFEST_FILE);
+            let Ok(manifest) = clientlib::load_manifest(&manifest_path.to_string_lossy()) else {
+                // possibly valid for another version of pre-commit: keep
+                // every checkout of this revision
+                let deps_prefix = format!("{}:", repo.repo);
+                unused_repos.retain(|(name, rev)| {
+                    *rev != repo.rev || (*name != repo.repo && !name.starts_with(&deps_prefix))
+                });
+                return;
+            };
+            unused_repos.remove(&key);
+
+            let by_id: HashMap<&str, &ManifestHook> = manifest
+                .iter()
+                .map(|hook| (hook.id.as_str(), hook))
+                .collect();
+            for hook in &repo.hooks {
+                let Some(manifest_hook) = by_id.get(hook.id.as_str()) else {
+                    continue;
+                };
+                let deps = hook
+                    .additional_dependencies
+                    .as_ref()
+                    .unwrap_or(&manifest_hook.additional_dependencies);
+                let name = store::Store::db_repo_name(&repo.repo, deps);
+                unused_repos.remove(&(name, repo.rev.clone()));
+            }
+        }
+    }
+}
+
+fn _gc(store: &store::Store) -> anyhow::Result<usize> {
+    let lock = store.exclusive_lock()?;
+    let conn = store.connect()?;
+
+    let mut all_repos: HashMap<RepoKey, path::PathBuf> = HashMap::new();
+    let mut stmt = conn.prepare("SELECT repo, ref, path FROM repos")?;
+    let mut rows = stmt.query(())?;
+    while let Some(row) = rows.next()? {
+        let path: String = row.get(2)?;
+        all_repos.insert((row.get(0)?, row.get(1)?), path.into());
+    }
+    let mut unused_repos: HashSet<RepoKey> = all_repos.keys().cloned().collect();
+
+    let mut configs: Vec<String> = Vec::new();
+    let mut stmt = conn.prepare("SELECT path FROM configs")?;
+    let mut rows = stmt.query(())?;
+    while let Some(row) = rows.next()? {
+        configs.push(row.get(0)?);
+    }
+
+    let mut dead_configs: Vec<&str> = Vec::new();
+    for config_path in &configs {
+        // missing or invalid configs no longer keep their repos alive
+        let Ok(config) = clientlib::load_config(config_path) else {
+            dead_configs.push(config_path);
+            continue;
+        };
+        for repo in &config.repos {
+            _mark_used_repos(&all_repos, &mut unused_repos, repo);
+        }
+    }
+
+    for config_path in dead_configs {
+        conn.execute("DELETE FROM configs WHERE path = ?", (config_path,))?;
+    }
+    let mut unused_repos: Vec<RepoKey> = unused_repos.into_iter().collect();
+    unused_repos.sort();
+    for key in &unused_repos {
+        let (repo, rev) = key;
+        conn.execute("DELETE FROM repos WHERE repo = ? and ref = ?", (repo, rev))?;
+        rm_rf::ensure_removed(&all_repos[key])?;
+    }
+
+    drop(lock);
+    Ok(unused_repos.len())
+}
+
+pub(crate) fn cmd(store: store::Store) -> anyhow::Result<i32> {
+    println!("{} repo(s) removed.", _gc(&store)?);
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::testing;
+
+    /// record a checkout of `repo` at `rev` without cloning anything
+    fn _fake_checkout(store: &store::Store, repo: &str, rev: &str) -> path::PathBuf {
+        let directory = tempfile::Builder::new()
+            .prefix("repo")
+            .tempdir_in(&store.directory)
+            .unwrap()
+            .into_path();
+        fs::write(directory.join(repository::MANIFEST_FILE), testing::MANIFEST).unwrap();
+        store
+            .connect()
+            .unwrap()
+            .execute(
+                "INSERT INTO repos (repo, ref, path) VALUES (?, ?, ?)",
+                (repo, rev, directory.to_string_lossy()),
+            )
+            .unwrap();
+        directory
+    }
+
+    fn _config(dir: &path::Path, name: &str, contents: &str, store: &store::Store) -> String {
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        let path = path.to_string_lossy().into_owned();
+        store.mark_config_used(&path).unwrap();
+        path
+    }
+
+    fn _uses(repo: &str, rev: &str) -> String {
+        format!("repos:\n-   repo: {repo}\n    rev: {rev}\n    hooks:\n    -   id: hook\n")
+    }
+
+    fn _configs(store: &store::Store) -> Vec<String> {
+        let conn = store.connect().unwrap();
+        let mut stmt = conn.prepare("SELECT path FROM configs").unwrap();
+        stmt.query_map((), |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn gc_keeps_repos_used_by_live_configs() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let store = store::Store::at(tmpdir.path().join("store")).unwrap();
+        let used = _fake_checkout(&store, "r", "v1");
+        let unused = _fake_checkout(&store, "r", "v2");
+        _config(tmpdir.path(), "live.yaml", &_uses("r", "v1"), &store);
+
+        assert_eq!(_gc(&store).unwrap(), 1);
+        assert!(used.exists());
+        assert!(!unused.exists());
+        assert_eq!(_configs(&store).len(), 1);
+
+        // nothing more to collect
+        assert_eq!(_gc(&store).unwrap(), 0);
+        assert!(used.exists());
+    }
+
+    #[test]
+    fn gc_drops_missing_configs() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let store = store::Store::at(tmpdir.path().join("store")).unwrap();
+        let checkout = _fake_checkout(&store, "r", "v1");
+        let config = _config(tmpdir.path(), "gone.yaml", &_uses("r", "v1"), &store);
+        fs::remove_file(config).unwrap();
+
+        assert_eq!(_gc(&store).unwrap(), 1);
+        assert!(!checkout.exists());
+        assert!(_configs(&store).is_empty());
+    }
+
+    #[test]
+    fn gc_drops_invalid_configs() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let store = store::Store::at(tmpdir.path().join("store")).unwrap();
+        let checkout = _fake_checkout(&store, "r", "v1");
+        let invalid = format!("{}        stages: [not-a-stage]\n", _uses("r", "v1"));
+        _config(tmpdir.path(), "invalid.yaml", &invalid, &store);
+        let live = _config(tmpdir.path(), "live.yaml", "repos: []\n", &store);
+
+        assert_eq!(_gc(&store).unwrap(), 1);
+        assert!(!checkout.exists());
+        let live = fs::canonicalize(live).unwrap();
+        assert_eq!(_configs(&store), [live.to_string_lossy()]);
+    }
+}
@@ -0,0 +1,263 @@
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::path;
+use std::process;
+
+use crate::commands::run;
+use crate::env_ext;
+use crate::store;
+use crate::HookImpl;
+use crate::HookType;
+use crate::Run;
+
+const Z40: &str = "0000000000000000000000000000000000000000";
+
+#[cfg(windows)]
+fn _executable(_: &path::Path) -> bool {
+    true
+}
+
+#[cfg(not(windows))]
+fn _executable(p: &path::Path) -> bool {
+    use faccess::PathExt;
+    p.executable()
+}
+
+fn _run_legacy(
+    hook_type: HookType,
+    hook_dir: &str,
+    args: &[String],
+) -> anyhow::Result<(i32, Vec<u8>)> {
+    if env_ext::var_os_nonempty("PRE_COMMIT_RUNNING_LEGACY").is_some() {
+        anyhow::bail!(
+            "bug: pre-commit's script is installed in migration mode\n\
+             run `pre-commit install -f --hook-type {hook_type}` to fix this\n\n\
+             Please report this bug at https://github.com/pre-commit/pre-commit-rs/issues"
+        );
+    }
+
+    let mut stdin = Vec::new();
+    if matches!(hook_type, HookType::PrePush) {
+        io::stdin().read_to_end(&mut stdin)?;
+    }
+
+    // not running in legacy mode
+    let legacy_hook = path::Path::new(hook_dir).join(format!("{hook_type}.legacy"));
+    if !_executable(&legacy_hook) {
+        return Ok((0, stdin));
+    }
+
+    let mut child = process::Command::new(&legacy_hook)
+        .args(args)
+        .env("PRE_COMMIT_RUNNING_LEGACY", "1")
+        .stdin(process::Stdio::piped())
+        .spawn()?;
+    // a hook may exit without reading its input
+    let _ = child.stdin.take().unwrap().write_all(&stdin);
+    let retv = child.wait()?.code().unwrap_or(255);
+    Ok((retv, stdin))
+}
+
+fn _validate_config(config: &str, skip_on_missing_config: bool) -> anyhow::Result<bool> {
+    if path::Path::new(config).is_file() {
+        Ok(true)
+    } else if skip_on_missing_config
+        || env_ext::var_os_nonempty("PRE_COMMIT_ALLOW_NO_CONFIG").is_some()
+    {
+        println!("`{config}` config file not found. Skipping `pre-commit`.");
+        Ok(false)
+    } else {
+        anyhow::bail!(
+            "No {config} file was found\n\
+             - To temporarily silence this, run `PRE_COMMIT_ALLOW_NO_CONFIG=1 git ...`\n\
+             - To permanently silence this, install pre-commit with the --allow-missing-config option\n\
+             - To uninstall pre-commit run `pre-commit uninstall`"
+        );
+    }
+}
+
+fn _ns(hook_type: HookType) -> Run {
+    Run {
+        hook: None,
+        verbose: false,
+        all_files: false,
+        files: Vec::new(),
+        show_diff_on_failure: false,
+        fail_fast: false,
+        ci: false,
+        from_ref: None,
+        to_ref: None,
+        hook_stage: hook_type.into(),
+        remote_branch: None,
+        local_branch: None,
+        commit_msg_filename: None,
+        prepare_commit_message_source: None,
+        commit_object_name: None,
+        remote_name: None,
+        remote_url: None,
+        checkout_type: None,
+        is_squash_merge: None,
+        rewrite_command: None,
+        pre_rebase_upstream: None,
+        pre_rebase_branch: None,
+    }
+}
+
+fn _git_output(args: &[&str]) -> anyhow::Result<String> {
+    let output = process::Command::new("git")
+        .args(args)
+        .stdin(process::Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed\n\nstderr: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+fn _rev_exists(rev: &str) -> anyhow::Result<bool> {
+    Ok(process::Command::new("git")
+        .args(["rev-list", "--quiet", rev])
+        .stdin(process::Stdio::null())
+        .stdout(process::Stdio::null())
+        .stderr(process::Stdio::null())
+        .status()?
+        .success())
+}
+
+fn _pre_push_ns(args: &[String], stdin: &[u8]) -> anyhow::Result<Option<Run>> {
+    let remote_name = &args[0];
+    let remote_url = &args[1];
+
+    let push_ns = |local_branch: &str, remote_branch: &str| {
+        let mut ns = _ns(HookType::PrePush);
+        ns.remote_name = Some(remote_name.clone());
+        ns.remote_url = Some(remote_url.clone());
+        ns.local_branch = Some(local_branch.into());
+        ns.remote_branch = Some(remote_branch.into());
+        ns
+    };
+
+    for line in String::from_utf8(stdin.to_vec())?.lines() {
+        let parts: Vec<&str> = line.rsplitn(4, ' ').collect();
+        let [remote_sha, remote_branch, local_sha, local_branch] = parts[..] else {
+            anyhow::bail!("unexpected pre-push input: {line:?}");
+        };
+
+        if local_sha == Z40 {
+            // deleting a remote branch, nothing to check
+            continue;
+        } else if remote_sha != Z40 && _rev_exists(remote_sha)? {
+            let mut ns = push_ns(local_branch, remote_branch);
+            ns.from_ref = Some(remote_sha.into());
+            ns.to_ref = Some(local_sha.into());
+            return Ok(Some(ns));
+        } else {
+            // ancestors not found in remote
+            let ancestors = _git_output(&[
+                "rev-list",
+                local_sha,
+                "--topo-order",
+                "--reverse",
+                "--not",
+                &format!("--remotes={remote_name}"),
+            ])?;
+            let Some(first_ancestor) = ancestors.lines().next() else {
+                continue;
+            };
+
+            let roots = _git_output(&["rev-list", "--max-parents=0", local_sha])?;
+            let mut ns = push_ns(local_branch, remote_branch);
+            if roots.lines().any(|root| root == first_ancestor) {
+                // pushing the whole tree including root commit
+                ns.all_files = true;
+            } else {
+                let source = _git_output(&["rev-parse", &format!("{first_ancestor}^")])?;
+                ns.from_ref = Some(source.trim().into());
+                ns.to_ref = Some(local_sha.into());
+            }
+            return Ok(Some(ns));
+        }
+    }
+
+    // nothing to push
+    Ok(None)
+}
+
+fn _check_args_length(hook_type: HookType, args: &[String]) -> anyhow::Result<()> {
+    let (ok, expected) = match hook_type {
+        HookType::PrepareCommitMsg => ((1..=3).contains(&args.len()), "1, 2, or 3 arguments"),
+        HookType::PreRebase => ((1..=2).contains(&args.len()), "1 or 2 arguments"),
+        HookType::CommitMsg | HookType::PostMerge | HookType::PostRewrite => {
+            (args.len() == 1, "1 argument")
+        }
+        HookType::PrePush => (args.len() == 2, "2 arguments"),
+        HookType::PostCheckout => (args.len() == 3, "3 arguments"),
+        HookType::PostCommit | HookType::PreCommit | HookType::PreMergeCommit => {
+            (args.is_empty(), "0 arguments")
+        }
+    };
+    if !ok {
+        anyhow::bail!(
+            "hook-impl for {hook_type} expected {expected} but got {}: {args:?}",
+            args.len(),
+        );
+    }
+    Ok(())
+}
+
+fn _run_ns(hook_type: HookType, args: &[String], stdin: &[u8]) -> anyhow::Result<Option<Run>> {
+    _check_args_length(hook_type, args)?;
+
+    let mut ns = _ns(hook_type);
+    match hook_type {
+        HookType::PrePush => return _pre_push_ns(args, stdin),
+        HookType::CommitMsg => {
+            ns.commit_msg_filename = Some(args[0].clone());
+        }
+        HookType::PrepareCommitMsg => {
+            ns.commit_msg_filename = Some(args[0].clone());
+            ns.prepare_commit_message_source = args.get(1).cloned();
+            ns.commit_object_name = args.get(2).cloned();
+        }
+        HookType::PostCommit | HookType::PreCommit | HookType::PreMergeCommit => {}
+        HookType::PostCheckout => {
+            ns.from_ref = Some(args[0].clone());
+            ns.to_ref = Some(args[1].clone());
+            ns.checkout_type = Some(args[2].clone());
+        }
+        HookType::PostMerge => {
+            ns.is_squash_merge = Some(args[0].clone());
+        }
+        HookType::PostRewrite => {
+            ns.rewrite_command = Some(args[0].clone());
+        }
+        HookType::PreRebase => {
+            ns.pre_rebase_upstream = Some(args[0].clone());
+            ns.pre_rebase_branch = args.get(1).cloned();
+        }
+    }
+    Ok(Some(ns))
+}
+
+pub(crate) fn cmd(
+    config: String,
+    repo: gix::Repository,
+    store: store::Store,
+    cmd: HookImpl,
+) -> anyhow::Result<i32> {
+    let (retv, stdin) = _run_legacy(cmd.hook_type, &cmd.hook_dir, &cmd.rest)?;
+
+    if !_validate_config(&config, cmd.skip_on_missing_config)? {
+        return Ok(retv);
+    }
+
+    match _run_ns(cmd.hook_type, &cmd.rest, &stdin)? {
+        Some(ns) => Ok(retv | run::cmd(config, repo, store, ns)?),
+        None => Ok(retv),
+    }
+}
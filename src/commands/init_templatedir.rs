@@ -0,0 +1,50 @@
+use std::fs;
+use std::path;
+use std::process;
+
+use crate::commands::install_uninstall;
+use crate::output;
+use crate::InitTemplatedir;
+
+fn _configured_template_dir() -> anyhow::Result<Option<path::PathBuf>> {
+    let output = process::Command::new("git")
+        .args(["config", "init.templateDir"])
+        .stdin(process::Stdio::null())
+        .stderr(process::Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let out = String::from_utf8(output.stdout)?;
+    let out = out.trim();
+    let configured = match (out.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => path::PathBuf::from(out),
+    };
+    Ok(fs::canonicalize(configured).ok())
+}
+
+pub(crate) fn cmd(config: &str, cmd: InitTemplatedir) -> anyhow::Result<i32> {
+    let directory = path::Path::new(&cmd.directory);
+    for hook_type in install_uninstall::hook_types(config, &cmd.hook_type) {
+        install_uninstall::install_hook_script(
+            config,
+            hook_type,
+            true,
+            !cmd.no_allow_missing_config,
+            directory,
+        )?;
+    }
+
+    let dest = fs::canonicalize(directory)?;
+    if _configured_template_dir()? != Some(dest.clone()) {
+        output::warning("`init.templateDir` not set to the target directory");
+        output::warning(format!(
+            "maybe `git config --global init.templateDir {}`?",
+            dest.display(),
+        ));
+    }
+
+    Ok(0)
+}
@@ -0,0 +1,206 @@
+use std::env;
+use std::fs;
+use std::path;
+
+use crate::clientlib;
+use crate::git;
+use crate::output;
+use crate::repository;
+use crate::store;
+use crate::HookType;
+use crate::Install;
+use crate::Uninstall;
+
+// This is used to identify the hook file we install
+const PRIOR_HASHES: &[&str] = &[
+    "4d9958c90bc262f47553e2c073f14cfe",
+    "d8ee923c46731b42cd95cc869add4062",
+    "49fd668cb42069aa1b6048464be5d395",
+    "79f09a650522a87b0da915d0d983b2de",
+    "e358c9dae00eac5d06b38dfdb1e33a8c",
+];
+const CURRENT_HASH: &str = "138fd403232d2ddd5efb44317e38bf03";
+const TEMPLATE_START: &str = "# start templated\n";
+const TEMPLATE_END: &str = "# end templated\n";
+
+static HOOK_TMPL: &str = r#"#!/usr/bin/env bash
+# File generated by pre-commit: https://pre-commit.com
+# ID: 138fd403232d2ddd5efb44317e38bf03
+
+# start templated
+INSTALL_EXE=/usr/bin/pre-commit-rs
+ARGS=(hook-impl --hook-type=pre-commit)
+# end templated
+
+HERE="$(cd "$(dirname "$0")" && pwd)"
+ARGS+=(--hook-dir "$HERE" -- "$@")
+
+if [ -x "$INSTALL_EXE" ]; then
+    exec "$INSTALL_EXE" "${ARGS[@]}"
+elif command -v pre-commit-rs > /dev/null; then
+    exec pre-commit-rs "${ARGS[@]}"
+else
+    echo '`pre-commit-rs` not found.  Did you forget to activate your virtualenv?' 1>&2
+    exit 1
+fi
+"#;
+
+pub(crate) fn hook_types(config: &str, hook_types: &[HookType]) -> Vec<HookType> {
+    if !hook_types.is_empty() {
+        hook_types.to_vec()
+    } else {
+        match clientlib::load_config(config) {
+            Ok(cfg) => cfg.default_install_hook_types,
+            Err(_) => vec![HookType::PreCommit],
+        }
+    }
+}
+
+fn _hook_paths(hook_type: HookType, git_dir: &path::Path) -> (path::PathBuf, path::PathBuf) {
+    let pth = git_dir.join("hooks").join(hook_type.to_string());
+    let legacy = git_dir.join("hooks").join(format!("{hook_type}.legacy"));
+    (pth, legacy)
+}
+
+fn _is_our_script(filename: &path::Path) -> bool {
+    match fs::read(filename) {
+        Ok(contents) => std::iter::once(&CURRENT_HASH)
+            .chain(PRIOR_HASHES)
+            .any(|h| contents.windows(h.len()).any(|w| w == h.as_bytes())),
+        Err(_) => false,
+    }
+}
+
+#[cfg(windows)]
+fn _make_executable(_: &path::Path) -> anyhow::Result<()> {
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn _make_executable(p: &path::Path) -> anyhow::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut perms = fs::metadata(p)?.permissions();
+    perms.set_mode(perms.mode() | 0o111);
+    fs::set_permissions(p, perms)?;
+    Ok(())
+}
+
+pub(crate) fn install_hook_script(
+    config: &str,
+    hook_type: HookType,
+    overwrite: bool,
+    skip_on_missing_config: bool,
+    git_dir: &path::Path,
+) -> anyhow::Result<()> {
+    let (hook_path, legacy_path) = _hook_paths(hook_type, git_dir);
+
+    fs::create_dir_all(hook_path.parent().unwrap())?;
+
+    // If we have an existing hook, move it to <hook>.legacy
+    if hook_path.symlink_metadata().is_ok() && !_is_our_script(&hook_path) {
+        fs::rename(&hook_path, &legacy_path)?;
+    }
+
+    // If we specify overwrite, we simply delete the legacy file
+    if overwrite && legacy_path.exists() {
+        fs::remove_file(&legacy_path)?;
+    } else if legacy_path.exists() {
+        println!(
+            "Running in migration mode with existing hooks at {}\nUse -f to use only pre-commit.",
+            legacy_path.display(),
+        );
+    }
+
+    let exe = env::current_exe()?;
+    let mut args = vec![
+        format!("--config={config}"),
+        "hook-impl".into(),
+        format!("--hook-type={hook_type}"),
+    ];
+    if skip_on_missing_config {
+        args.push("--skip-on-missing-config".into());
+    }
+
+    let (before, rest) = HOOK_TMPL.split_once(TEMPLATE_START).unwrap();
+    let (_, after) = rest.split_once(TEMPLATE_END).unwrap();
+    let contents = format!(
+        "{before}{TEMPLATE_START}INSTALL_EXE={}\nARGS=({})\n{TEMPLATE_END}{after}",
+        shlex::try_quote(&exe.to_string_lossy())?,
+        shlex::try_join(args.iter().map(String::as_str))?,
+    );
+    fs::write(&hook_path, contents)?;
+    _make_executable(&hook_path)?;
+
+    println!("pre-commit installed at {}", hook_path.display());
+    Ok(())
+}
+
+pub(crate) fn install_hooks(config: &str, store: &store::Store) -> anyhow::Result<i32> {
+    let config = clientlib::load_config(config)?;
+    let hooks = repository::all_hooks(&config, store)?;
+    repository::install_hook_envs(&hooks.iter().collect::<Vec<_>>(), store)?;
+    Ok(0)
+}
+
+pub(crate) fn install(
+    config: &str,
+    repo: &gix::Repository,
+    store: &store::Store,
+    cmd: Install,
+) -> anyhow::Result<i32> {
+    if git::has_core_hookpaths_set(repo) {
+        output::error(
+            "Cowardly refusing to install hooks with `core.hooksPath` set.\n\
+             hint: `git config --unset-all core.hooksPath`",
+        );
+        return Ok(1);
+    }
+
+    let git_dir = git::get_git_common_dir(repo)?;
+    for hook_type in hook_types(config, &cmd.hook_type) {
+        install_hook_script(
+            config,
+            hook_type,
+            cmd.overwrite,
+            cmd.allow_missing_config,
+            &git_dir,
+        )?;
+    }
+
+    if cmd.install_hooks {
+        install_hooks(config, store)?;
+    }
+
+    Ok(0)
+}
+
+fn _uninstall_hook_script(hook_type: HookType, git_dir: &path::Path) -> anyhow::Result<()> {
+    let (hook_path, legacy_path) = _hook_paths(hook_type, git_dir);
+
+    // If our file doesn't exist or it isn't ours, gtfo.
+    if !_is_our_script(&hook_path) {
+        return Ok(());
+    }
+
+    fs::remove_file(&hook_path)?;
+    println!("{hook_type} uninstalled");
+
+    if legacy_path.exists() {
+        fs::rename(&legacy_path, &hook_path)?;
+        println!("Restored previous hooks to {}", hook_path.display());
+    }
+
+    Ok(())
+}
+
+pub(crate) fn uninstall(
+    config: &str,
+    repo: &gix::Repository,
+    cmd: Uninstall,
+) -> anyhow::Result<i32> {
+    let git_dir = git::get_git_common_dir(repo)?;
+    for hook_type in hook_types(config, &cmd.hook_type) {
+        _uninstall_hook_script(hook_type, &git_dir)?;
+    }
+    Ok(0)
+}
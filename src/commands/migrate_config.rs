@@ -0,0 +1,349 @@
+use std::fs;
+
+use yaml_rust2::parser::{MarkedEventReceiver, Parser};
+use yaml_rust2::scanner::{Marker, TScalarStyle};
+use yaml_rust2::Event;
+
+const STAGE_RENAMES: [(&str, &str); 3] = [
+    ("commit", "pre-commit"),
+    ("merge-commit", "pre-merge-commit"),
+    ("push", "pre-push"),
+];
+
+/// a yaml node along with its position (in chars) in the original source
+enum Node {
+    Scalar {
+        value: String,
+        style: TScalarStyle,
+        start: usize,
+    },
+    Sequence(Vec<Node>),
+    Mapping(Vec<(Node, Node)>),
+    Alias,
+}
+
+impl Node {
+    fn get(&self, key: &str) -> Option<&Node> {
+        let Node::Mapping(pairs) = self else {
+            return None;
+        };
+        pairs.iter().find_map(|(k, v)| match k {
+            Node::Scalar { value, .. } if value == key => Some(v),
+            _ => None,
+        })
+    }
+
+    fn items(&self) -> &[Node] {
+        match self {
+            Node::Sequence(items) => items,
+            _ => &[],
+        }
+    }
+}
+
+#[derive(Default)]
+struct Events(Vec<(Event, Marker)>);
+
+impl MarkedEventReceiver for Events {
+    fn on_event(&mut self, ev: Event, mark: Marker) {
+        self.0.push((ev, mark));
+    }
+}
+
+fn _compose_node<I: Iterator<Item = (Event, Marker)>>(events: &mut I) -> Option<Node> {
+    let (ev, mark) = events.next()?;
+    match ev {
+        Event::Scalar(value, style, ..) => Some(Node::Scalar {
+            value,
+            style,
+            start: mark.index(),
+        }),
+        Event::SequenceStart(..) => {
+            let mut items = Vec::new();
+            while let Some(item) = _compose_node(events) {
+                items.push(item);
+            }
+            Some(Node::Sequence(items))
+        }
+        Event::MappingStart(..) => {
+            let mut pairs = Vec::new();
+            while let Some(key) = _compose_node(events) {
+                pairs.push((key, _compose_node(events)?));
+            }
+            Some(Node::Mapping(pairs))
+        }
+        Event::Alias(_) => Some(Node::Alias),
+        Event::SequenceEnd | Event::MappingEnd => None,
+        _ => _compose_node(events),
+    }
+}
+
+fn _compose(contents: &str) -> anyhow::Result<Option<Node>> {
+    let mut events = Events::default();
+    Parser::new_from_str(contents).load(&mut events, false)?;
+    Ok(_compose_node(&mut events.0.into_iter()))
+}
+
+fn _is_header_line(line: &str) -> bool {
+    line.starts_with('#') || line.starts_with("---") || line.trim().is_empty()
+}
+
+fn _migrate_map(contents: String) -> anyhow::Result<String> {
+    let value: serde_yaml::Value = serde_yaml::from_str(&contents)?;
+    if !value.is_sequence() {
+        return Ok(contents);
+    }
+
+    let lines: Vec<&str> = contents.split_inclusive('\n').collect();
+    let i = lines
+        .iter()
+        .position(|line| !_is_header_line(line))
+        .unwrap_or(lines.len());
+    let header = lines[..i].concat();
+    let rest = lines[i..].concat();
+
+    // If they are using the "default" flow style of yaml, this operation
+    // will yield a valid configuration
+    let trial_contents = format!("{header}repos:\n{rest}");
+    if serde_yaml::from_str::<serde_yaml::Value>(&trial_contents).is_ok() {
+        Ok(trial_contents)
+    } else {
+        let indented: String = lines[i..]
+            .iter()
+            .map(|line| {
+                if line.trim().is_empty() {
+                    line.to_string()
+                } else {
+                    format!("    {line}")
+                }
+            })
+            .collect();
+        Ok(format!("{header}repos:\n{indented}"))
+    }
+}
+
+fn _stage_rewrites(stages: Option<&Node>, rewrites: &mut Vec<(usize, String, String)>) {
+    for stage in stages.map_or(&[][..], Node::items) {
+        let Node::Scalar {
+            value,
+            style,
+            start,
+        } = stage
+        else {
+            continue;
+        };
+        let Some((_, new)) = STAGE_RENAMES.iter().find(|(old, _)| old == value) else {
+            continue;
+        };
+        let quote = match style {
+            TScalarStyle::Plain => "",
+            TScalarStyle::SingleQuoted => "'",
+            TScalarStyle::DoubleQuoted => "\"",
+            _ => continue,
+        };
+        rewrites.push((
+            *start,
+            format!("{quote}{value}{quote}"),
+            format!("{quote}{new}{quote}"),
+        ));
+    }
+}
+
+fn _migrate_composed(contents: String) -> anyhow::Result<String> {
+    let Some(tree) = _compose(&contents)? else {
+        return Ok(contents);
+    };
+
+    // (start in chars, original source, replacement)
+    let mut rewrites: Vec<(usize, String, String)> = Vec::new();
+    _stage_rewrites(tree.get("default_stages"), &mut rewrites);
+    for repo in tree.get("repos").map_or(&[][..], Node::items) {
+        for hook in repo.get("hooks").map_or(&[][..], Node::items) {
+            _stage_rewrites(hook.get("stages"), &mut rewrites);
+        }
+    }
+    rewrites.sort_by_key(|(start, ..)| *start);
+
+    let mut ret = String::with_capacity(contents.len());
+    let mut end = 0;
+    for (start, orig, replacement) in &rewrites {
+        let start = contents
+            .char_indices()
+            .nth(*start)
+            .map_or(contents.len(), |(i, _)| i);
+        // escaped scalars are left alone rather than mangled
+        if !contents[start..].starts_with(orig.as_str()) {
+            continue;
+        }
+        ret.push_str(&contents[end..start]);
+        ret.push_str(replacement);
+        end = start + orig.len();
+    }
+    ret.push_str(&contents[end..]);
+    Ok(ret)
+}
+
+pub(crate) fn migrate_config(config: &str, quiet: bool) -> anyhow::Result<()> {
+    let orig_contents = fs::read_to_string(config)?;
+
+    let contents = _migrate_map(orig_contents.clone())
+        .and_then(_migrate_composed)
+        .map_err(|e| anyhow::anyhow!(cfgv::ctx_s(&[format!("File {config}")], e)))?;
+
+    if contents != orig_contents {
+        fs::write(config, contents)?;
+        println!("Configuration has been migrated.");
+    } else if !quiet {
+        println!("Configuration is already migrated.");
+    }
+    Ok(())
+}
+
+pub(crate) fn cmd(config: &str) -> anyhow::Result<i32> {
+    migrate_config(config, false)?;
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn _migrate(contents: &str) -> String {
+        _migrate_map(contents.to_string())
+            .and_then(_migrate_composed)
+            .unwrap()
+    }
+
+    #[test]
+    fn migrate_map_already_a_map() {
+        let contents = "repos:\n-   repo: local\n    hooks: []\n";
+        assert_eq!(_migrate_map(contents.into()).unwrap(), contents);
+    }
+
+    #[test]
+    fn migrate_map_list() {
+        let contents = "\
+# a header comment
+
+-   repo: local
+    hooks:
+    -   id: foo
+        name: foo
+        entry: ./bin/foo.sh
+        language: script
+";
+        assert_eq!(
+            _migrate_map(contents.into()).unwrap(),
+            "\
+# a header comment
+
+repos:
+-   repo: local
+    hooks:
+    -   id: foo
+        name: foo
+        entry: ./bin/foo.sh
+        language: script
+",
+        );
+    }
+
+    #[test]
+    fn migrate_map_flow_list_is_indented() {
+        let contents = "\
+[{
+    repo: local,
+    hooks: [{
+        id: foo, name: foo, entry: ./bin/foo.sh,
+        language: script,
+    }]
+}]
+";
+        let ret = _migrate_map(contents.into()).unwrap();
+        assert_eq!(
+            ret,
+            "\
+repos:
+    [{
+        repo: local,
+        hooks: [{
+            id: foo, name: foo, entry: ./bin/foo.sh,
+            language: script,
+        }]
+    }]
+",
+        );
+        let value: serde_yaml::Value = serde_yaml::from_str(&ret).unwrap();
+        assert_eq!(value["repos"][0]["hooks"][0]["id"], "foo");
+    }
+
+    #[test]
+    fn migrate_composed_stages() {
+        let contents = "\
+default_stages: [commit, 'push']
+repos:
+-   repo: local
+    hooks:
+    -   id: a
+        stages: [commit, \"merge-commit\", manual]
+    -   id: b
+        stages:
+        -   'commit'
+        -   \"push\"
+        -   pre-push
+";
+        assert_eq!(
+            _migrate_composed(contents.into()).unwrap(),
+            "\
+default_stages: [pre-commit, 'pre-push']
+repos:
+-   repo: local
+    hooks:
+    -   id: a
+        stages: [pre-commit, \"pre-merge-commit\", manual]
+    -   id: b
+        stages:
+        -   'pre-commit'
+        -   \"pre-push\"
+        -   pre-push
+",
+        );
+    }
+
+    #[test]
+    fn migrate_composed_leaves_other_values() {
+        // only stage names are rewritten, not other scalars which match
+        let contents = "\
+repos:
+-   repo: local
+    hooks:
+    -   id: commit
+        name: push
+        entry: commit
+        language: system
+";
+        assert_eq!(_migrate_composed(contents.into()).unwrap(), contents);
+    }
+
+    #[test]
+    fn migrate_is_idempotent() {
+        let contents = "\
+-   repo: local
+    hooks:
+    -   id: a
+        stages: [commit, push]
+";
+        let once = _migrate(contents);
+        assert_eq!(
+            once,
+            "\
+repos:
+-   repo: local
+    hooks:
+    -   id: a
+        stages: [pre-commit, pre-push]
+",
+        );
+        assert_eq!(_migrate(&once), once);
+    }
+}
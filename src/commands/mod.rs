@@ -1,5 +1,12 @@
+pub(crate) mod autoupdate;
 pub(crate) mod clean;
+pub(crate) mod gc;
+pub(crate) mod hook_impl;
+pub(crate) mod init_templatedir;
+pub(crate) mod install_uninstall;
+pub(crate) mod migrate_config;
 pub(crate) mod run;
 pub(crate) mod sample_config;
+pub(crate) mod try_repo;
 pub(crate) mod validate_config;
 pub(crate) mod validate_manifest;
@@ -1,22 +1,365 @@
+use std::cell::RefCell;
+use std::collections::{hash_map, HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path;
+use std::time;
+
+use crate::clientlib;
+use crate::clientlib::{Config, Regex, TypeTag};
+use crate::color;
 use crate::env_ext;
 use crate::git;
+use crate::hook::Hook;
+use crate::identify;
+use crate::languages;
+use crate::output;
+use crate::repository;
 use crate::staged_files_only;
 use crate::store;
 use crate::PreCommitEnv;
 use crate::Run;
 use crate::Stage;
 
+fn _all_filenames(repo: &gix::Repository, cmd: &Run) -> anyhow::Result<Vec<String>> {
+    match cmd.hook_stage {
+        // these hooks do not operate on files
+        Stage::PostCheckout
+        | Stage::PostCommit
+        | Stage::PostMerge
+        | Stage::PostRewrite
+        | Stage::PreRebase => return Ok(Vec::new()),
+        Stage::PrepareCommitMsg | Stage::CommitMsg => {
+            return Ok(cmd.commit_msg_filename.iter().cloned().collect());
+        }
+        _ => {}
+    }
+
+    if let (Some(from_ref), Some(to_ref)) = (&cmd.from_ref, &cmd.to_ref) {
+        git::get_changed_files(repo, from_ref, to_ref)
+    } else if !cmd.files.is_empty() {
+        Ok(cmd.files.clone())
+    } else if cmd.all_files {
+        git::get_all_files(repo)
+    } else {
+        git::get_staged_files(repo)
+    }
+}
+
+struct Classifier {
+    filenames: Vec<String>,
+    tags: RefCell<HashMap<String, identify::Tags>>,
+}
+
+fn _filter_by_include_exclude<'a>(
+    filenames: impl IntoIterator<Item = &'a String>,
+    include: &Regex,
+    exclude: &Regex,
+) -> anyhow::Result<Vec<&'a String>> {
+    let mut ret = Vec::new();
+    for filename in filenames {
+        if include.search(filename)? && !exclude.search(filename)? {
+            ret.push(filename);
+        }
+    }
+    Ok(ret)
+}
+
+impl Classifier {
+    fn from_config(
+        filenames: Vec<String>,
+        include: &Regex,
+        exclude: &Regex,
+    ) -> anyhow::Result<Self> {
+        // deleted files (for instance from `--from-ref` / `--to-ref`) are skipped
+        let filenames = _filter_by_include_exclude(&filenames, include, exclude)?
+            .into_iter()
+            .filter(|filename| fs::symlink_metadata(filename).is_ok())
+            .cloned()
+            .collect();
+        Ok(Classifier {
+            filenames,
+            tags: RefCell::new(HashMap::new()),
+        })
+    }
+
+    fn _matches_types(&self, filename: &str, hook: &Hook) -> anyhow::Result<bool> {
+        let mut cache = self.tags.borrow_mut();
+        let tags = match cache.entry(filename.into()) {
+            hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            hash_map::Entry::Vacant(entry) => {
+                entry.insert(identify::tags_from_path(path::Path::new(filename))?)
+            }
+        };
+        let has = |tag: &TypeTag| tags.contains(tag.0.as_str());
+        Ok(hook.types.iter().all(has)
+            && (hook.types_or.is_empty() || hook.types_or.iter().any(has))
+            && !hook.exclude_types.iter().any(has))
+    }
+
+    fn filenames_for_hook(&self, hook: &Hook) -> anyhow::Result<Vec<String>> {
+        let mut ret = Vec::new();
+        for filename in _filter_by_include_exclude(&self.filenames, &hook.files, &hook.exclude)? {
+            if self._matches_types(filename, hook)? {
+                ret.push(filename.clone());
+            }
+        }
+        Ok(ret)
+    }
+}
+
+fn _get_skips() -> HashSet<String> {
+    let skips = env::var("SKIP").unwrap_or_default();
+    skips
+        .split(',')
+        .map(str::trim)
+        .filter(|skip| !skip.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+const SKIPPED: &str = "Skipped";
+const NO_FILES: &str = "(no files to check)";
+const NOT_RUN: &str = "(not run)";
+
+fn _start_msg(start: &str, cols: usize, end_len: usize) -> String {
+    let dots = ".".repeat(cols.saturating_sub(start.chars().count() + end_len + 1));
+    format!("{start}{dots}")
+}
+
+fn _full_msg(
+    start: &str,
+    cols: usize,
+    end_msg: &str,
+    end_color: &str,
+    use_color: bool,
+    postfix: &str,
+) -> String {
+    let dots =
+        ".".repeat(cols.saturating_sub(start.chars().count() + postfix.len() + end_msg.len() + 1));
+    let end = color::format_color(end_msg, end_color, use_color);
+    format!("{start}{dots}{postfix}{end}")
+}
+
+fn _subtle_line<W: Write>(w: &mut W, s: &str, use_color: bool) -> io::Result<()> {
+    writeln!(w, "{}", color::format_color(s, color::SUBTLE, use_color))
+}
+
+/// Compute the number of columns to display hook messages.  The widest
+/// that will be displayed is in the no files skipped case:
+///
+///     Hook name...(no files to check) Skipped
+fn _compute_cols(hooks: &[Hook]) -> usize {
+    let name_len = hooks
+        .iter()
+        .map(|hook| hook.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let cols = name_len + 3 + NO_FILES.len() + 1 + SKIPPED.len();
+    cols.max(80)
+}
+
+/// append a hook's output to its `log_file` in a single write so output of
+/// concurrent writers does not interleave
+fn _append_log(log_file: &str, out: &[u8]) -> anyhow::Result<()> {
+    let log_file = path::Path::new(log_file);
+    if let Some(parent) = log_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut contents = out.to_vec();
+    contents.push(b'\n');
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_file)?
+        .write_all(&contents)?;
+    Ok(())
+}
+
+/// the state shared by the hooks of a single run
+struct RunState<'a> {
+    repo: &'a gix::Repository,
+    skips: &'a HashSet<String>,
+    cols: usize,
+    verbose: bool,
+    use_color: bool,
+    /// the worktree changes before the next hook runs
+    diff: git::Diff,
+}
+
+fn _run_single_hook(
+    state: &mut RunState,
+    hook: &Hook,
+    filenames: &[String],
+) -> anyhow::Result<bool> {
+    let RunState {
+        repo,
+        skips,
+        cols,
+        verbose,
+        use_color,
+        ..
+    } = *state;
+    let mut stdout = io::stdout().lock();
+
+    let duration;
+    let retcode;
+    let out;
+    let files_modified;
+    if skips.contains(&hook.id) || skips.contains(&hook.alias) {
+        let msg = _full_msg(&hook.name, cols, SKIPPED, color::YELLOW, use_color, "");
+        writeln!(stdout, "{msg}")?;
+        duration = None;
+        retcode = 0;
+        out = Vec::new();
+        files_modified = false;
+    } else if filenames.is_empty() && !hook.always_run {
+        let msg = _full_msg(
+            &hook.name,
+            cols,
+            SKIPPED,
+            color::TURQUOISE,
+            use_color,
+            NO_FILES,
+        );
+        writeln!(stdout, "{msg}")?;
+        duration = None;
+        retcode = 0;
+        out = Vec::new();
+        files_modified = false;
+    } else {
+        // print hook and dots first in case the hook takes a while to run
+        write!(stdout, "{}", _start_msg(&hook.name, cols, 6))?;
+        stdout.flush()?;
+
+        let filenames = if hook.pass_filenames { filenames } else { &[] };
+        let time_before = time::Instant::now();
+        (retcode, out) = languages::get(&hook.language)?.run_hook(hook, filenames)?;
+        duration = Some(time_before.elapsed());
+
+        let diff_after = git::diff(repo)?;
+        // if the hook makes changes, fail the commit
+        files_modified = state.diff != diff_after;
+        state.diff = diff_after;
+
+        let (status, print_color) = if retcode != 0 || files_modified {
+            ("Failed", color::RED)
+        } else {
+            ("Passed", color::GREEN)
+        };
+        writeln!(
+            stdout,
+            "{}",
+            color::format_color(status, print_color, use_color)
+        )?;
+    }
+
+    let out = out.trim_ascii();
+    if !hook.log_file.is_empty() && !out.is_empty() {
+        _append_log(&hook.log_file, out)?;
+    }
+
+    let verbose = verbose || hook.verbose;
+    if verbose || retcode != 0 || files_modified {
+        _subtle_line(&mut stdout, &format!("- hook id: {}", hook.id), use_color)?;
+
+        if let (true, Some(duration)) = (verbose, duration) {
+            let msg = format!("- duration: {:.2}s", duration.as_secs_f64());
+            _subtle_line(&mut stdout, &msg, use_color)?;
+        }
+
+        if retcode != 0 {
+            let msg = format!("- exit code: {retcode}");
+            _subtle_line(&mut stdout, &msg, use_color)?;
+        }
+
+        // print a message if failing due to file modifications
+        if files_modified {
+            let msg = "- files were modified by this hook";
+            _subtle_line(&mut stdout, msg, use_color)?;
+        }
+
+        if !out.is_empty() {
+            stdout.write_all(b"\n")?;
+            stdout.write_all(out)?;
+            stdout.write_all(b"\n\n")?;
+        }
+    }
+
+    Ok(retcode != 0 || files_modified)
+}
+
+fn _run_hooks(
+    config: &Config,
+    repo: &gix::Repository,
+    cmd: &Run,
+    hooks: &[Hook],
+    skips: &HashSet<String>,
+) -> anyhow::Result<i32> {
+    let classifier =
+        Classifier::from_config(_all_filenames(repo, cmd)?, &config.files, &config.exclude)?;
+    let cols = _compute_cols(hooks);
+    let use_color = output::use_color();
+
+    let mut state = RunState {
+        repo,
+        skips,
+        cols,
+        verbose: cmd.verbose,
+        use_color,
+        diff: git::diff(repo)?,
+    };
+    let mut retval = 0;
+    for (i, hook) in hooks.iter().enumerate() {
+        let filenames = classifier.filenames_for_hook(hook)?;
+        if _run_single_hook(&mut state, hook, &filenames)? {
+            retval = 1;
+            if config.fail_fast || hook.fail_fast || cmd.fail_fast {
+                for hook in &hooks[i + 1..] {
+                    let msg = _full_msg(
+                        &hook.name,
+                        cols,
+                        SKIPPED,
+                        color::TURQUOISE,
+                        use_color,
+                        NOT_RUN,
+                    );
+                    println!("{msg}");
+                }
+                break;
+            }
+        }
+    }
+
+    if retval != 0 && cmd.show_diff_on_failure && !state.diff.is_empty() {
+        if cmd.all_files {
+            println!(
+                "pre-commit hook(s) made changes.\n\
+                 If you are seeing this message in CI, reproduce locally with: \
+                 `pre-commit run --all-files`.\n\
+                 To run `pre-commit` as part of git workflow, use `pre-commit install`."
+            );
+        }
+        println!("All changes made by hooks:");
+        git::print_diff(repo, use_color)?;
+    }
+
+    Ok(retval)
+}
+
 pub(crate) fn cmd(
     config: String,
     repo: gix::Repository,
     store: store::Store,
     cmd: Run,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<i32> {
     // prevent recursive post-checkout hooks (#1418)
     if matches!(cmd.hook_stage, Stage::PostCommit)
         && env_ext::var_os_nonempty(staged_files_only::SKIP_POST_CHECKOUT).is_some()
     {
-        return Ok(());
+        return Ok(0);
     }
 
     let stash = !cmd.all_files && cmd.files.is_empty();
@@ -30,15 +373,86 @@ pub(crate) fn cmd(
     }
 
     cmd.set_pre_commit_env_vars();
+    // so hooks can match our color output
+    let color = if output::use_color() {
+        "always"
+    } else {
+        "never"
+    };
+    env::set_var("PRE_COMMIT_COLOR", color);
 
     let mut ctx: Option<staged_files_only::StagedFilesOnly> = None;
     if stash {
         ctx = Some(staged_files_only::StagedFilesOnly::new(
             &repo,
-            store.directory,
+            &store.directory,
         )?);
     }
 
+    let config = clientlib::load_config(&config)?;
+    let hooks: Vec<Hook> = repository::all_hooks(&config, &store)?
+        .into_iter()
+        .filter(|hook| {
+            cmd.hook
+                .as_ref()
+                .is_none_or(|id| hook.id == *id || hook.alias == *id)
+                && hook.runs_in_stage(cmd.hook_stage)
+        })
+        .collect();
+
+    if let (Some(id), true) = (&cmd.hook, hooks.is_empty()) {
+        println!("No hook with id `{id}` in stage `{}`", cmd.hook_stage);
+        return Ok(1);
+    }
+
+    let mut skips = _get_skips();
+    if cmd.ci {
+        skips.extend(config.ci.settings.skip.iter().cloned());
+    }
+    let to_install: Vec<&Hook> = hooks
+        .iter()
+        .filter(|hook| !skips.contains(&hook.id) && !skips.contains(&hook.alias))
+        .collect();
+    repository::install_hook_envs(&to_install, &store)?;
+
+    let ret = _run_hooks(&config, &repo, &cmd, &hooks, &skips)?;
+
     drop(ctx);
-    Ok(())
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn _filter(filenames: &[&str], include: &str, exclude: &str) -> Vec<String> {
+        let filenames: Vec<String> = filenames.iter().map(|s| s.to_string()).collect();
+        let include = Regex::new(include).unwrap();
+        let exclude = Regex::new(exclude).unwrap();
+        _filter_by_include_exclude(&filenames, &include, &exclude)
+            .unwrap()
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn filter_by_include_exclude() {
+        let filenames = ["setup.py", "src/a.py", "src/b.txt", "tests/a_test.py"];
+        assert_eq!(_filter(&filenames, "", "^$"), filenames);
+        // patterns are searched for, not matched from the start
+        assert_eq!(
+            _filter(&filenames, r"\.py$", "^$"),
+            ["setup.py", "src/a.py", "tests/a_test.py"],
+        );
+        assert_eq!(
+            _filter(&filenames, "a", "^$"),
+            ["src/a.py", "tests/a_test.py"]
+        );
+        assert_eq!(
+            _filter(&filenames, r"\.py$", "test"),
+            ["setup.py", "src/a.py"],
+        );
+        assert_eq!(_filter(&filenames, "^src/", r"\.txt$"), ["src/a.py"]);
+    }
 }
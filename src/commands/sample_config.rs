@@ -11,7 +11,7 @@ repos:
     -   id: check-added-large-files
 ";
 
-pub(crate) fn cmd() -> anyhow::Result<()> {
+pub(crate) fn cmd() -> anyhow::Result<i32> {
     print!("{SAMPLE_CONFIG}");
-    Ok(())
+    Ok(0)
 }
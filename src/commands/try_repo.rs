@@ -0,0 +1,126 @@
+use std::fs;
+use std::path;
+
+use crate::clientlib;
+use crate::commands::run;
+use crate::git;
+use crate::output;
+use crate::repository;
+use crate::store;
+use crate::TryRepo;
+
+fn _head_rev(remote: &str) -> anyhow::Result<String> {
+    let out = git::cmd_output(".", &["ls-remote", "--exit-code", remote, "HEAD"])?;
+    match out.split_whitespace().next() {
+        Some(rev) => Ok(rev.into()),
+        None => anyhow::bail!("could not determine `HEAD` of {remote}"),
+    }
+}
+
+fn _has_changes(repo: &path::Path) -> bool {
+    git::cmd_output(repo, &["status", "--porcelain", "--untracked-files=all"])
+        .is_ok_and(|out| !out.is_empty())
+}
+
+/// snapshot uncommitted (and untracked) changes of an on-disk repository
+/// into a temporary clone so they can be tried before committing
+fn _repo_ref(
+    tmpdir: &path::Path,
+    repo: &str,
+    rev: Option<String>,
+) -> anyhow::Result<(String, String)> {
+    // if `rev` is explicitly passed, use it
+    if let Some(rev) = rev {
+        return Ok((repo.into(), rev));
+    }
+
+    let rev = _head_rev(repo)?;
+    let Ok(repo_path) = fs::canonicalize(repo) else {
+        return Ok((repo.into(), rev));
+    };
+    if !repo_path.is_dir() || !_has_changes(&repo_path) {
+        return Ok((repo.into(), rev));
+    }
+
+    output::warning("Creating temporary repo with uncommitted changes...");
+
+    let shadow = tmpdir.join("shadow-repo");
+    let shadow_s = shadow.to_string_lossy();
+    git::cmd_output(tmpdir, &["clone", &repo_path.to_string_lossy(), &shadow_s])?;
+    git::cmd_output(&shadow, &["checkout", &rev, "-b", "_pc_tmp"])?;
+    // stage the original working tree into the shadow repository's index
+    git::cmd_output(
+        &shadow,
+        &["--work-tree", &repo_path.to_string_lossy(), "add", "-A"],
+    )?;
+    git::cmd_output(
+        &shadow,
+        &[
+            "-c",
+            "user.name=pre-commit",
+            "-c",
+            "user.email=pre-commit@localhost",
+            "commit",
+            "--no-edit",
+            "--no-gpg-sign",
+            "--allow-empty",
+            "-n",
+            "-minit",
+        ],
+    )?;
+
+    let rev = _head_rev(&shadow_s)?;
+    Ok((shadow_s.into_owned(), rev))
+}
+
+pub(crate) fn cmd(repo: gix::Repository, cmd: TryRepo) -> anyhow::Result<i32> {
+    let tempdir = tempfile::tempdir()?;
+    let (hook_repo, rev) = _repo_ref(tempdir.path(), &cmd.repo, cmd.rev)?;
+
+    let store = store::Store::at(tempdir.path().join("store"))?;
+    let ids: Vec<String> = match &cmd.run.hook {
+        Some(hook) => vec![hook.clone()],
+        None => {
+            let repo_path = store.clone(&hook_repo, &rev, &[])?;
+            let manifest_path = repo_path.join(repository::MANIFEST_FILE);
+            let mut manifest = clientlib::load_manifest(&manifest_path.to_string_lossy())?;
+            manifest.sort_by(|a, b| a.id.cmp(&b.id));
+            manifest.into_iter().map(|hook| hook.id).collect()
+        }
+    };
+
+    let hooks: serde_yaml::Sequence = ids
+        .into_iter()
+        .map(|id| {
+            let mut hook = serde_yaml::Mapping::new();
+            hook.insert("id".into(), id.into());
+            hook.into()
+        })
+        .collect();
+    let mut repo_config = serde_yaml::Mapping::new();
+    repo_config.insert("repo".into(), hook_repo.into());
+    repo_config.insert("rev".into(), rev.into());
+    repo_config.insert("hooks".into(), hooks.into());
+    let mut config = serde_yaml::Mapping::new();
+    config.insert(
+        "repos".into(),
+        serde_yaml::Value::Sequence(vec![repo_config.into()]),
+    );
+    let config_s = serde_yaml::to_string(&config)?;
+
+    let config_filename = tempdir.path().join(".pre-commit-config.yaml");
+    fs::write(&config_filename, &config_s)?;
+
+    println!("{}", "=".repeat(79));
+    println!("Using config:");
+    println!("{}", "=".repeat(79));
+    print!("{config_s}");
+    println!("{}", "=".repeat(79));
+
+    run::cmd(
+        config_filename.to_string_lossy().into(),
+        repo,
+        store,
+        cmd.run,
+    )
+}
@@ -1,8 +1,8 @@
 use crate::clientlib;
 
-pub(crate) fn cmd(cmd: crate::ValidateFiles) -> anyhow::Result<()> {
+pub(crate) fn cmd(cmd: crate::ValidateFiles) -> anyhow::Result<i32> {
     for filename in cmd.filenames {
         clientlib::load_config(&filename)?;
     }
-    Ok(())
+    Ok(0)
 }
@@ -1,6 +1,8 @@
-pub(crate) fn cmd(cmd: crate::ValidateFiles) -> anyhow::Result<()> {
+use crate::clientlib;
+
+pub(crate) fn cmd(cmd: crate::ValidateFiles) -> anyhow::Result<i32> {
     for filename in cmd.filenames {
-        cfgv::load_file::<Vec<crate::clientlib::ManifestHook>>(&filename)?;
+        clientlib::load_manifest(&filename)?;
     }
-    Ok(())
+    Ok(0)
 }
@@ -1,4 +1,6 @@
+use std::env;
 use std::ffi;
+use std::fs;
 use std::path;
 use std::process;
 
@@ -11,8 +13,30 @@ pub(crate) fn repo<P: AsRef<path::Path>>(p: P) -> anyhow::Result<gix::Repository
     Ok(repo)
 }
 
+/// the index git is operating on: `git commit -a` and `git commit <paths>`
+/// point hooks at a temporary index through `GIT_INDEX_FILE`
+pub(crate) fn index(
+    repo: &gix::Repository,
+) -> anyhow::Result<gix::worktree::IndexPersistedOrInMemory> {
+    let Some(index_file) = env::var_os("GIT_INDEX_FILE").filter(|s| !s.is_empty()) else {
+        return Ok(repo.index_or_empty()?.into());
+    };
+    let index_file = path::PathBuf::from(index_file);
+    if !index_file.exists() {
+        let state = gix::index::State::new(repo.object_hash());
+        return Ok(gix::index::File::from_state(state, index_file).into());
+    }
+    let index = gix::index::File::at(
+        index_file,
+        repo.object_hash(),
+        false,
+        gix::index::decode::Options::default(),
+    )?;
+    Ok(index.into())
+}
+
 pub(crate) fn has_unmerged_paths(repo: &gix::Repository) -> anyhow::Result<bool> {
-    for entry in repo.index()?.entries() {
+    for entry in index(repo)?.entries() {
         if entry.flags.stage() != gix::index::entry::Stage::Unconflicted {
             return Ok(true);
         }
@@ -44,3 +68,227 @@ pub(crate) fn has_unstaged_config(repo: &gix::Repository, config: &str) -> anyho
 
     Ok(retc == 1)
 }
+
+pub(crate) fn get_staged_files(repo: &gix::Repository) -> anyhow::Result<Vec<String>> {
+    let index = index(repo)?;
+    // a freshly initialized repository has everything staged as an addition
+    let head_tree = repo.head_tree_id_or_empty()?;
+    let mut ret = Vec::new();
+    repo.tree_index_status(
+        &head_tree,
+        &index,
+        None,
+        gix::status::tree_index::TrackRenames::Disabled,
+        |change, _, _| -> Result<_, std::convert::Infallible> {
+            // Everything except for deletions
+            match change {
+                gix::diff::index::ChangeRef::Deletion { .. } => {}
+                change => ret.push(change.fields().0.to_string()),
+            }
+            Ok(gix::diff::index::Action::Continue)
+        },
+    )?;
+    Ok(ret)
+}
+
+pub(crate) fn get_all_files(repo: &gix::Repository) -> anyhow::Result<Vec<String>> {
+    let index = index(repo)?;
+    let mut ret: Vec<String> = index
+        .entries()
+        .iter()
+        .map(|entry| entry.path(&index).to_string())
+        .collect();
+    // unmerged paths have an entry per stage
+    ret.dedup();
+    Ok(ret)
+}
+
+fn _peel_to_commit<'repo>(
+    repo: &'repo gix::Repository,
+    rev: &str,
+) -> anyhow::Result<gix::Commit<'repo>> {
+    Ok(repo.rev_parse_single(rev)?.object()?.peel_to_commit()?)
+}
+
+pub(crate) fn get_changed_files(
+    repo: &gix::Repository,
+    old: &str,
+    new: &str,
+) -> anyhow::Result<Vec<String>> {
+    let old = _peel_to_commit(repo, old)?;
+    let new = _peel_to_commit(repo, new)?;
+    // `old...new`: changes on `new` since it diverged from `old`, or the
+    // full difference when the histories are unrelated
+    let base = match repo.merge_base(old.id, new.id) {
+        Ok(base) => base.object()?.peel_to_commit()?,
+        Err(_) => old,
+    };
+    let changes = repo.diff_tree_to_tree(&base.tree()?, &new.tree()?, None)?;
+    let mut ret: Vec<String> = changes
+        .iter()
+        .filter(|change| !change.entry_mode().is_tree())
+        .map(|change| change.location().to_string())
+        .collect();
+    ret.sort();
+    Ok(ret)
+}
+
+fn _worktree_id(repo: &gix::Repository, rela_path: &bstr::BStr) -> anyhow::Result<gix::ObjectId> {
+    let path = repo
+        .workdir()
+        .unwrap()
+        .join(gix::path::from_bstr(rela_path));
+    let contents = if fs::symlink_metadata(&path)?.is_symlink() {
+        gix::path::into_bstr(fs::read_link(&path)?)
+            .into_owned()
+            .into()
+    } else {
+        fs::read(&path)?
+    };
+    Ok(gix::objs::compute_hash(
+        repo.object_hash(),
+        gix::objs::Kind::Blob,
+        &contents,
+    )?)
+}
+
+/// the unstaged changes in the working tree: each differing path along with
+/// its current contents (`None` when removed) and whether its executable bit
+/// was flipped, so that further changes to an already modified file show up
+#[derive(PartialEq, Eq)]
+pub(crate) struct Diff(Vec<(bstr::BString, Option<(gix::ObjectId, bool)>)>);
+
+impl Diff {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+pub(crate) fn diff(repo: &gix::Repository) -> anyhow::Result<Diff> {
+    use gix::status::index_worktree::Item;
+    use gix::status::plumbing::index_as_worktree::{Change, EntryStatus};
+
+    let changes = repo
+        .status(gix::progress::Discard)?
+        .index(index(repo)?)
+        .untracked_files(gix::status::UntrackedFiles::None)
+        .index_worktree_rewrites(None)
+        .index_worktree_submodules(gix::status::Submodule::Given {
+            ignore: gix::submodule::config::Ignore::All,
+            check_dirty: false,
+        })
+        .into_index_worktree_iter(Vec::new())?;
+
+    let mut ret = Vec::new();
+    for change in changes {
+        let Item::Modification {
+            rela_path, status, ..
+        } = change?
+        else {
+            continue;
+        };
+        let state = match status {
+            EntryStatus::Change(Change::Removed) => None,
+            EntryStatus::Change(Change::Type { .. }) => {
+                Some((_worktree_id(repo, rela_path.as_ref())?, false))
+            }
+            EntryStatus::Change(Change::Modification {
+                executable_bit_changed,
+                ..
+            }) => Some((
+                _worktree_id(repo, rela_path.as_ref())?,
+                executable_bit_changed,
+            )),
+            // stat-only changes, conflicts and submodules are not in `git diff`
+            _ => continue,
+        };
+        ret.push((rela_path, state));
+    }
+    ret.sort();
+    Ok(Diff(ret))
+}
+
+pub(crate) fn print_diff(repo: &gix::Repository, use_color: bool) -> anyhow::Result<()> {
+    let color = if use_color { "always" } else { "never" };
+    process::Command::new("git")
+        .arg_kv("-C", repo.workdir().unwrap())
+        .args(["--no-pager", "diff", "--no-ext-diff"])
+        .arg(format!("--color={color}"))
+        .stdin(process::Stdio::null())
+        .status()?;
+    Ok(())
+}
+
+pub(crate) fn get_git_common_dir(repo: &gix::Repository) -> anyhow::Result<path::PathBuf> {
+    let common_dir = repo.common_dir();
+    Ok(pathdiff::diff_paths(common_dir, env::current_dir()?)
+        .unwrap_or_else(|| common_dir.to_path_buf()))
+}
+
+pub(crate) fn has_core_hookpaths_set(repo: &gix::Repository) -> bool {
+    repo.config_snapshot()
+        .string("core.hooksPath")
+        .is_some_and(|s| !s.is_empty())
+}
+
+/// Too many bugs dealing with environment variables and GIT:
+/// https://github.com/pre-commit/pre-commit/issues/300
+/// In git 2.6.3 (maybe others), git exports GIT_WORK_TREE while running
+/// pre-commit hooks
+/// In git 1.9.1 (maybe others), git exports GIT_DIR and GIT_INDEX_FILE
+/// while running pre-commit hooks in submodules.
+/// GIT_DIR: Causes git clone to clone wrong thing
+/// GIT_INDEX_FILE: Causes 'error invalid object ...' during commit
+pub(crate) fn no_git_env(cmd: &mut process::Command) -> &mut process::Command {
+    for (k, _) in env::vars_os() {
+        let k_s = k.to_string_lossy();
+        if k_s.starts_with("GIT_")
+            && !k_s.starts_with("GIT_CONFIG_KEY_")
+            && !k_s.starts_with("GIT_CONFIG_VALUE_")
+            && !matches!(
+                k_s.as_ref(),
+                "GIT_EXEC_PATH"
+                    | "GIT_SSH"
+                    | "GIT_SSH_COMMAND"
+                    | "GIT_SSL_CAINFO"
+                    | "GIT_SSL_NO_VERIFY"
+                    | "GIT_CONFIG_COUNT"
+                    | "GIT_HTTP_PROXY_AUTHORIZATION"
+                    | "GIT_ALLOW_PROTOCOL"
+                    | "GIT_ASKPASS"
+            )
+        {
+            cmd.env_remove(&k);
+        }
+    }
+    cmd
+}
+
+/// run `git` in `cwd`, isolated from the calling repository's environment
+pub(crate) fn cmd_output<P: AsRef<path::Path>>(cwd: P, args: &[&str]) -> anyhow::Result<String> {
+    let output = no_git_env(&mut process::Command::new("git"))
+        .current_dir(cwd)
+        .args(args)
+        .stdin(process::Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed\n\nstdout: {}\nstderr: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr),
+        );
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+pub(crate) fn init_repo(p: &path::Path, remote: &str) -> anyhow::Result<()> {
+    let remote = match fs::canonicalize(remote) {
+        Ok(remote) if remote.is_dir() => remote.to_string_lossy().into_owned(),
+        _ => remote.into(),
+    };
+    // avoid the user's template so that hooks do not recurse
+    cmd_output(p, &["init", "--template="])?;
+    cmd_output(p, &["remote", "add", "origin", &remote])?;
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use std::ops::Deref;
+use std::path;
+
+use crate::clientlib::ManifestHook;
+use crate::Stage;
+
+#[derive(Debug)]
+pub(crate) struct Hook {
+    /// the `repo:` the hook was configured from (`local`, a url, ...)
+    pub(crate) src: String,
+    /// the directory the hook's repository is checked out to
+    pub(crate) prefix: path::PathBuf,
+    pub(crate) hook: ManifestHook,
+}
+
+impl Hook {
+    /// hooks sharing this key share an installed environment
+    pub(crate) fn install_key(&self) -> (&path::Path, &str, &str, &[String]) {
+        (
+            &self.prefix,
+            &self.language,
+            &self.language_version,
+            &self.additional_dependencies,
+        )
+    }
+
+    pub(crate) fn runs_in_stage(&self, stage: Stage) -> bool {
+        self.stages.contains(&stage)
+    }
+}
+
+impl Deref for Hook {
+    type Target = ManifestHook;
+
+    fn deref(&self) -> &ManifestHook {
+        &self.hook
+    }
+}
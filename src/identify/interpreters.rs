@@ -0,0 +1,27 @@
+// sorted by key so lookups can binary search
+
+/// tags for executables by their shebang interpreter
+pub(crate) static INTERPRETERS: &[(&str, &[&str])] = &[
+    ("ash", &["ash", "shell"]),
+    ("awk", &["awk"]),
+    ("bash", &["bash", "shell"]),
+    ("bats", &["bash", "bats", "shell"]),
+    ("cbsd", &["cbsd", "shell"]),
+    ("csh", &["csh", "shell"]),
+    ("dash", &["dash", "shell"]),
+    ("expect", &["expect"]),
+    ("ksh", &["ksh", "shell"]),
+    ("node", &["javascript"]),
+    ("nodejs", &["javascript"]),
+    ("perl", &["perl"]),
+    ("php", &["php"]),
+    ("php7", &["php", "php7"]),
+    ("php8", &["php", "php8"]),
+    ("python", &["python"]),
+    ("python2", &["python", "python2"]),
+    ("python3", &["python", "python3"]),
+    ("ruby", &["ruby"]),
+    ("sh", &["sh", "shell"]),
+    ("tcsh", &["shell", "tcsh"]),
+    ("zsh", &["shell", "zsh"]),
+];
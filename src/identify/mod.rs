@@ -0,0 +1,444 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::io::{BufRead, Read};
+use std::path;
+
+use faccess::PathExt;
+
+mod extensions;
+mod interpreters;
+
+pub(crate) const DIRECTORY: &str = "directory";
+pub(crate) const SYMLINK: &str = "symlink";
+pub(crate) const SOCKET: &str = "socket";
+pub(crate) const FILE: &str = "file";
+pub(crate) const EXECUTABLE: &str = "executable";
+pub(crate) const NON_EXECUTABLE: &str = "non-executable";
+pub(crate) const TEXT: &str = "text";
+pub(crate) const BINARY: &str = "binary";
+
+const BASE_TAGS: [&str; 8] = [
+    DIRECTORY,
+    SYMLINK,
+    SOCKET,
+    FILE,
+    EXECUTABLE,
+    NON_EXECUTABLE,
+    TEXT,
+    BINARY,
+];
+
+pub(crate) type Tags = HashSet<&'static str>;
+
+fn _lookup(table: &'static [(&str, &[&str])], key: &str) -> Option<&'static [&'static str]> {
+    table
+        .binary_search_by_key(&key, |(k, _)| k)
+        .ok()
+        .map(|i| table[i].1)
+}
+
+pub(crate) fn is_known_tag(tag: &str) -> bool {
+    BASE_TAGS.contains(&tag)
+        || [
+            extensions::EXTENSIONS,
+            extensions::EXTENSIONS_NEED_BINARY_CHECK,
+            extensions::NAMES,
+            interpreters::INTERPRETERS,
+        ]
+        .iter()
+        .flat_map(|table| table.iter())
+        .any(|(_, tags)| tags.contains(&tag))
+}
+
+#[cfg(unix)]
+fn _is_socket(file_type: &fs::FileType) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    file_type.is_socket()
+}
+
+#[cfg(not(unix))]
+fn _is_socket(_file_type: &fs::FileType) -> bool {
+    false
+}
+
+pub(crate) fn tags_from_path(path: &path::Path) -> anyhow::Result<Tags> {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        anyhow::bail!("{} does not exist.", path.display());
+    };
+
+    let file_type = metadata.file_type();
+    if file_type.is_dir() {
+        return Ok(Tags::from([DIRECTORY]));
+    } else if file_type.is_symlink() {
+        return Ok(Tags::from([SYMLINK]));
+    } else if _is_socket(&file_type) {
+        return Ok(Tags::from([SOCKET]));
+    }
+
+    let mut tags = Tags::from([FILE]);
+
+    let executable = path.executable();
+    tags.insert(if executable {
+        EXECUTABLE
+    } else {
+        NON_EXECUTABLE
+    });
+
+    // As an optimization, if we're able to read tags from the filename, then
+    // we don't peek at the file contents.
+    let by_name = path
+        .file_name()
+        .map(|name| tags_from_filename(&name.to_string_lossy()))
+        .unwrap_or_default();
+    if !by_name.is_empty() {
+        tags.extend(by_name);
+    } else if executable {
+        if let Some(interpreter) = parse_shebang_from_file(path)?.first() {
+            tags.extend(tags_from_interpreter(interpreter));
+        }
+    }
+
+    // some extensions can be both binary and text
+    // see EXTENSIONS_NEED_BINARY_CHECK
+    if !tags.contains(TEXT) && !tags.contains(BINARY) {
+        tags.insert(if file_is_text(path)? { TEXT } else { BINARY });
+    }
+
+    Ok(tags)
+}
+
+pub(crate) fn tags_from_filename(filename: &str) -> Tags {
+    let mut ret = Tags::new();
+
+    // Allow e.g. "Dockerfile.xenial" to match "Dockerfile"
+    for part in std::iter::once(filename).chain(filename.split('.')) {
+        if let Some(tags) = _lookup(extensions::NAMES, part) {
+            ret.extend(tags);
+            break;
+        }
+    }
+
+    // matches python's `os.path.splitext`: leading dots are not extensions
+    let ext = filename
+        .trim_start_matches('.')
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_lowercase());
+    if let Some(ext) = ext.filter(|ext| !ext.is_empty()) {
+        if let Some(tags) = _lookup(extensions::EXTENSIONS, &ext)
+            .or_else(|| _lookup(extensions::EXTENSIONS_NEED_BINARY_CHECK, &ext))
+        {
+            ret.extend(tags);
+        }
+    }
+
+    ret
+}
+
+pub(crate) fn tags_from_interpreter(interpreter: &str) -> Tags {
+    let mut interpreter = interpreter.rsplit('/').next().unwrap_or(interpreter);
+
+    // Try "python3.5.2" => "python3.5" => "python3" until one matches.
+    while !interpreter.is_empty() {
+        if let Some(tags) = _lookup(interpreters::INTERPRETERS, interpreter) {
+            return tags.iter().copied().collect();
+        }
+        interpreter = interpreter.rsplit_once('.').map_or("", |(head, _)| head);
+    }
+
+    Tags::new()
+}
+
+/// Return whether the first KB of contents seems to be non-binary text.
+pub(crate) fn is_text<R: Read>(r: R) -> io::Result<bool> {
+    let mut buf = Vec::with_capacity(1024);
+    r.take(1024).read_to_end(&mut buf)?;
+    Ok(buf
+        .iter()
+        .all(|&b| matches!(b, 7..=13 | 27 | 0x20..=0x7e | 0x80..=0xff)))
+}
+
+pub(crate) fn file_is_text(path: &path::Path) -> anyhow::Result<bool> {
+    Ok(is_text(fs::File::open(path)?)?)
+}
+
+fn _is_printable(s: &str) -> bool {
+    // python's `string.printable`
+    s.chars()
+        .all(|c| c.is_ascii_graphic() || matches!(c, ' ' | '\t' | '\n' | '\r' | '\x0b' | '\x0c'))
+}
+
+fn _shebang_split(line: &str) -> Vec<String> {
+    // shebangs aren't supposed to be quoted, though some tools such as
+    // setuptools will write them with quotes so we'll best-guess parse
+    // with shlex first
+    shlex::split(line).unwrap_or_else(|| line.split_whitespace().map(String::from).collect())
+}
+
+fn _read_shebang_line<R: BufRead>(r: &mut R) -> io::Result<Option<String>> {
+    let mut start = [0u8; 2];
+    let mut n = 0;
+    while n < start.len() {
+        match r.read(&mut start[n..])? {
+            0 => break,
+            read => n += read,
+        }
+    }
+    if &start[..n] != b"#!" {
+        return Ok(None);
+    }
+
+    let mut line = Vec::new();
+    r.read_until(b'\n', &mut line)?;
+    match String::from_utf8(line) {
+        Ok(line) if _is_printable(&line) => Ok(Some(line)),
+        _ => Ok(None),
+    }
+}
+
+fn _parse_nix_shebang<R: BufRead>(r: &mut R, mut cmd: Vec<String>) -> io::Result<Vec<String>> {
+    while let Some(line) = _read_shebang_line(r)? {
+        let tokens = _shebang_split(line.trim());
+        for (i, token) in tokens
+            .iter()
+            .enumerate()
+            .take(tokens.len().saturating_sub(1))
+        {
+            if token == "-i" {
+                // the argument to -i flag
+                cmd = vec![tokens[i + 1].clone()];
+            }
+        }
+    }
+    Ok(cmd)
+}
+
+/// Parse the shebang from a file opened for reading binary.
+pub(crate) fn parse_shebang<R: BufRead>(mut r: R) -> io::Result<Vec<String>> {
+    let Some(line) = _read_shebang_line(&mut r)? else {
+        return Ok(Vec::new());
+    };
+
+    let mut cmd = _shebang_split(line.trim());
+    if cmd.len() >= 2 && cmd[0] == "/usr/bin/env" && cmd[1] == "-S" {
+        cmd.drain(..2);
+    } else if cmd.first().is_some_and(|exe| exe == "/usr/bin/env") {
+        cmd.remove(0);
+    }
+
+    if cmd == ["nix-shell"] {
+        return _parse_nix_shebang(&mut r, cmd);
+    }
+
+    Ok(cmd)
+}
+
+/// Parse the shebang given a file path.
+pub(crate) fn parse_shebang_from_file(path: &path::Path) -> anyhow::Result<Vec<String>> {
+    if fs::symlink_metadata(path).is_err() {
+        anyhow::bail!("{} does not exist.", path.display());
+    } else if !path.executable() {
+        return Ok(Vec::new());
+    }
+
+    let f = fs::File::open(path)?;
+    Ok(parse_shebang(io::BufReader::new(f))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn _tags(tags: &[&'static str]) -> Tags {
+        tags.iter().copied().collect()
+    }
+
+    fn _shebang(contents: &[u8]) -> Vec<String> {
+        parse_shebang(contents).unwrap()
+    }
+
+    #[test]
+    fn tables_are_sorted() {
+        for table in [
+            extensions::EXTENSIONS,
+            extensions::EXTENSIONS_NEED_BINARY_CHECK,
+            extensions::NAMES,
+            interpreters::INTERPRETERS,
+        ] {
+            for pair in table.windows(2) {
+                assert!(pair[0].0 < pair[1].0, "{} < {}", pair[0].0, pair[1].0);
+            }
+        }
+    }
+
+    #[test]
+    fn known_tags() {
+        assert!(is_known_tag(FILE));
+        assert!(is_known_tag("python3"));
+        assert!(is_known_tag("dockerfile"));
+        assert!(!is_known_tag("not-a-tag"));
+    }
+
+    #[test]
+    fn filename_extension() {
+        assert_eq!(tags_from_filename("x.py"), _tags(&["python", "text"]));
+        assert_eq!(
+            tags_from_filename("x.PNG"),
+            _tags(&["binary", "image", "png"])
+        );
+        assert_eq!(tags_from_filename("x.cfg"), _tags(&["text"]));
+        assert_eq!(tags_from_filename("x.plist"), _tags(&["plist"]));
+        assert_eq!(tags_from_filename("x.unknown"), Tags::new());
+        assert_eq!(tags_from_filename("noext"), Tags::new());
+        // a leading dot is not an extension
+        assert_eq!(tags_from_filename(".py"), Tags::new());
+    }
+
+    #[test]
+    fn filename_special_names() {
+        assert_eq!(
+            tags_from_filename("Dockerfile"),
+            _tags(&["dockerfile", "text"])
+        );
+        assert_eq!(
+            tags_from_filename("Dockerfile.xenial"),
+            _tags(&["dockerfile", "text"]),
+        );
+        assert_eq!(
+            tags_from_filename(".pdbrc"),
+            _tags(&["pdbrc", "python", "text"]),
+        );
+        // the name and the extension both contribute
+        assert_eq!(tags_from_filename("setup.cfg"), _tags(&["ini", "text"]));
+        assert_eq!(
+            tags_from_filename("Dockerfile.py"),
+            _tags(&["dockerfile", "python", "text"]),
+        );
+    }
+
+    #[test]
+    fn interpreter() {
+        assert_eq!(
+            tags_from_interpreter("python3"),
+            _tags(&["python", "python3"])
+        );
+        assert_eq!(
+            tags_from_interpreter("/usr/bin/python3.12"),
+            _tags(&["python", "python3"]),
+        );
+        assert_eq!(tags_from_interpreter("python"), _tags(&["python"]));
+        assert_eq!(tags_from_interpreter("not-an-interpreter"), Tags::new());
+        assert_eq!(tags_from_interpreter(""), Tags::new());
+    }
+
+    #[test]
+    fn shebang() {
+        assert_eq!(_shebang(b"#!/usr/bin/env python3\n"), ["python3"]);
+        assert_eq!(
+            _shebang(b"#!/usr/bin/python3 -u\n"),
+            ["/usr/bin/python3", "-u"]
+        );
+        assert_eq!(_shebang(b"#! /usr/bin/env  bash  \nset -e\n"), ["bash"]);
+        assert_eq!(
+            _shebang(b"#!\"/path with/python\"\n"),
+            ["/path with/python"]
+        );
+        assert!(_shebang(b"").is_empty());
+        assert!(_shebang(b"#").is_empty());
+        assert!(_shebang(b"print('hi')\n").is_empty());
+        // not printable
+        assert!(_shebang(b"#!/usr/bin/\x01python\n").is_empty());
+    }
+
+    #[test]
+    fn shebang_env_split() {
+        assert_eq!(
+            _shebang(b"#!/usr/bin/env -S python3 -u\n"),
+            ["python3", "-u"],
+        );
+        assert_eq!(_shebang(b"#!/usr/bin/env -S\n"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn shebang_nix_shell() {
+        let contents = b"#!/usr/bin/env nix-shell\n#! nix-shell -i bash -p hello\necho hi\n";
+        assert_eq!(_shebang(contents), ["bash"]);
+        assert_eq!(_shebang(b"#!/usr/bin/env nix-shell\n"), ["nix-shell"]);
+    }
+
+    #[test]
+    fn text_detection() {
+        assert!(is_text(&b"hello world\n"[..]).unwrap());
+        assert!(is_text(&b""[..]).unwrap());
+        assert!(is_text("h\u{e9}llo \u{2603}\n".as_bytes()).unwrap());
+        assert!(is_text(&b"\x1b[1mbold\x1b[0m\n"[..]).unwrap());
+        assert!(!is_text(&b"hello\0world"[..]).unwrap());
+        assert!(!is_text(&b"\x7fELF\x02\x01\x01\x00"[..]).unwrap());
+
+        // only the first KB is considered
+        let mut contents = vec![b'a'; 1024];
+        contents.push(0);
+        assert!(is_text(&contents[..]).unwrap());
+    }
+
+    #[test]
+    fn path_missing() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        assert!(tags_from_path(&tmpdir.path().join("missing")).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn path_tags() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmp = tmpdir.path();
+
+        assert_eq!(tags_from_path(tmp).unwrap(), _tags(&[DIRECTORY]));
+
+        fs::write(tmp.join("f.py"), "print('hi')\n").unwrap();
+        assert_eq!(
+            tags_from_path(&tmp.join("f.py")).unwrap(),
+            _tags(&[FILE, NON_EXECUTABLE, TEXT, "python"]),
+        );
+
+        symlink("f.py", tmp.join("link")).unwrap();
+        assert_eq!(
+            tags_from_path(&tmp.join("link")).unwrap(),
+            _tags(&[SYMLINK])
+        );
+        // broken symlinks are still symlinks
+        symlink("missing", tmp.join("broken")).unwrap();
+        assert_eq!(
+            tags_from_path(&tmp.join("broken")).unwrap(),
+            _tags(&[SYMLINK])
+        );
+
+        fs::write(tmp.join("data"), b"\x00\x01\x02").unwrap();
+        assert_eq!(
+            tags_from_path(&tmp.join("data")).unwrap(),
+            _tags(&[FILE, NON_EXECUTABLE, BINARY]),
+        );
+
+        // executables without a known name are identified by their shebang
+        let script = tmp.join("script");
+        fs::write(&script, "#!/usr/bin/env python3\nprint('hi')\n").unwrap();
+        assert_eq!(
+            tags_from_path(&script).unwrap(),
+            _tags(&[FILE, NON_EXECUTABLE, TEXT]),
+        );
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(
+            tags_from_path(&script).unwrap(),
+            _tags(&[FILE, EXECUTABLE, TEXT, "python", "python3"]),
+        );
+        assert_eq!(parse_shebang_from_file(&script).unwrap(), ["python3"]);
+
+        // the name takes precedence over the shebang
+        let named = tmp.join("x.sh");
+        fs::write(&named, "#!/usr/bin/env python3\n").unwrap();
+        fs::set_permissions(&named, fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(!tags_from_path(&named).unwrap().contains("python"));
+    }
+}
@@ -0,0 +1,15 @@
+use crate::hook::Hook;
+use crate::languages::Language;
+
+pub(crate) struct Fail;
+
+impl Language for Fail {
+    fn run_hook(&self, hook: &Hook, file_args: &[String]) -> anyhow::Result<(i32, Vec<u8>)> {
+        let mut out = format!("{}\n\n", hook.entry);
+        for filename in file_args {
+            out.push_str(filename);
+            out.push('\n');
+        }
+        Ok((1, out.into_bytes()))
+    }
+}
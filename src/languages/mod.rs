@@ -0,0 +1,140 @@
+use std::env;
+use std::ffi;
+use std::path;
+
+use crate::hook::Hook;
+use crate::xargs;
+
+mod fail;
+mod python;
+mod script;
+mod system;
+
+pub(crate) trait Language: Sync {
+    /// The directory name (within the hook's repository) environments are
+    /// installed to, languages without environments return `None`.
+    fn environment_dir(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn install_environment(
+        &self,
+        _prefix: &path::Path,
+        _version: &str,
+        _additional_dependencies: &[String],
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Returns a description of the problem if an installed environment is
+    /// no longer usable (for instance the interpreter was removed).
+    fn health_check(&self, _prefix: &path::Path, _version: &str) -> Option<String> {
+        None
+    }
+
+    fn run_hook(&self, hook: &Hook, file_args: &[String]) -> anyhow::Result<(i32, Vec<u8>)>;
+}
+
+pub(crate) fn get(language: &str) -> anyhow::Result<&'static dyn Language> {
+    match language {
+        "fail" => Ok(&fail::Fail),
+        "python" => Ok(&python::Python),
+        "script" | "unsupported_script" => Ok(&script::Script),
+        "system" | "unsupported" => Ok(&system::System),
+        _ => anyhow::bail!("language `{language}` is not supported"),
+    }
+}
+
+pub(crate) fn environment_dir(prefix: &path::Path, env_dir: &str, version: &str) -> path::PathBuf {
+    prefix.join(format!("{env_dir}-{version}"))
+}
+
+pub(crate) fn hook_cmd(entry: &str, args: &[String]) -> anyhow::Result<Vec<String>> {
+    let Some(mut cmd) = shlex::split(entry) else {
+        anyhow::bail!("`entry` has unbalanced quoting: {entry}");
+    };
+    cmd.extend(args.iter().cloned());
+    Ok(cmd)
+}
+
+const FIXED_RANDOM_SEED: u64 = 1542676187;
+
+fn target_concurrency() -> usize {
+    if env::var_os("PRE_COMMIT_NO_CONCURRENCY").is_some() {
+        1
+    } else if env::var_os("TRAVIS").is_some() {
+        // Travis appears to have a bunch of CPUs, but we can't use them all.
+        2
+    } else {
+        xargs::cpu_count()
+    }
+}
+
+/// Deterministically shuffle
+fn _shuffled(seq: &[String]) -> Vec<String> {
+    let mut ret = seq.to_vec();
+    // splitmix64, reseeded identically every time
+    let mut state = FIXED_RANDOM_SEED;
+    let mut next = || {
+        state = state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    };
+    for i in (1..ret.len()).rev() {
+        let j = (next() % (i as u64 + 1)) as usize;
+        ret.swap(i, j);
+    }
+    ret
+}
+
+pub(crate) fn run_xargs(
+    cmd: &[String],
+    file_args: &[String],
+    require_serial: bool,
+    envs: &[(&str, ffi::OsString)],
+) -> anyhow::Result<(i32, Vec<u8>)> {
+    if require_serial {
+        xargs::xargs(cmd, file_args, 1, envs)
+    } else {
+        // Shuffle the files so that they more evenly fill out the xargs
+        // partitions, but do it deterministically in case a hook cares about
+        // ordering.
+        xargs::xargs(cmd, &_shuffled(file_args), target_concurrency(), envs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn _strs(parts: &[&str]) -> Vec<String> {
+        parts.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn shuffled_is_a_permutation() {
+        let seq = _strs(&["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"]);
+        let mut ret = _shuffled(&seq);
+        assert_ne!(ret, seq);
+        ret.sort();
+        assert_eq!(ret, seq);
+    }
+
+    #[test]
+    fn shuffled_is_deterministic() {
+        let seq = _strs(&["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"]);
+        assert_eq!(_shuffled(&seq), _shuffled(&seq));
+        assert_eq!(
+            _shuffled(&seq),
+            _strs(&["a", "e", "f", "d", "i", "h", "g", "b", "j", "c"]),
+        );
+    }
+
+    #[test]
+    fn shuffled_trivial() {
+        assert_eq!(_shuffled(&[]), Vec::<String>::new());
+        assert_eq!(_shuffled(&_strs(&["a"])), _strs(&["a"]));
+    }
+}
@@ -0,0 +1,101 @@
+use std::env;
+use std::ffi;
+use std::iter;
+use std::path;
+use std::process;
+
+use crate::hook::Hook;
+use crate::languages::{environment_dir, hook_cmd, run_xargs, Language};
+
+const ENVIRONMENT_DIR: &str = "py_env";
+
+#[cfg(windows)]
+const BIN_DIR: &str = "Scripts";
+#[cfg(not(windows))]
+const BIN_DIR: &str = "bin";
+
+pub(crate) struct Python;
+
+fn _bin_dir(prefix: &path::Path, version: &str) -> path::PathBuf {
+    environment_dir(prefix, ENVIRONMENT_DIR, version).join(BIN_DIR)
+}
+
+fn _interpreter(version: &str) -> &str {
+    if version == "default" {
+        if cfg!(windows) {
+            "python"
+        } else {
+            "python3"
+        }
+    } else {
+        version
+    }
+}
+
+fn _check_call(cmd: &mut process::Command) -> anyhow::Result<()> {
+    let output = cmd.stdin(process::Stdio::null()).output()?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "command: {cmd:?}\nreturn code: {}\nstdout:\n{}\nstderr:\n{}",
+            output.status.code().unwrap_or(255),
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr),
+        );
+    }
+    Ok(())
+}
+
+impl Language for Python {
+    fn environment_dir(&self) -> Option<&'static str> {
+        Some(ENVIRONMENT_DIR)
+    }
+
+    fn install_environment(
+        &self,
+        prefix: &path::Path,
+        version: &str,
+        additional_dependencies: &[String],
+    ) -> anyhow::Result<()> {
+        let envdir = environment_dir(prefix, ENVIRONMENT_DIR, version);
+        _check_call(
+            process::Command::new(_interpreter(version))
+                .args(["-m", "venv"])
+                .arg(&envdir),
+        )?;
+        _check_call(
+            process::Command::new(_bin_dir(prefix, version).join("python"))
+                .args(["-m", "pip", "install", "."])
+                .args(additional_dependencies)
+                .current_dir(prefix),
+        )?;
+        Ok(())
+    }
+
+    fn health_check(&self, prefix: &path::Path, version: &str) -> Option<String> {
+        let python = _bin_dir(prefix, version).join("python");
+        match process::Command::new(&python)
+            .args(["-c", "pass"])
+            .stdin(process::Stdio::null())
+            .output()
+        {
+            Ok(output) if output.status.success() => None,
+            Ok(output) => Some(String::from_utf8_lossy(&output.stderr).into()),
+            Err(e) => Some(format!("{}: {e}", python.display())),
+        }
+    }
+
+    fn run_hook(&self, hook: &Hook, file_args: &[String]) -> anyhow::Result<(i32, Vec<u8>)> {
+        let envdir = environment_dir(&hook.prefix, ENVIRONMENT_DIR, &hook.language_version);
+        let orig_path = env::var_os("PATH").unwrap_or_default();
+        let path =
+            env::join_paths(iter::once(envdir.join(BIN_DIR)).chain(env::split_paths(&orig_path)))?;
+        let envs: [(&str, ffi::OsString); 2] =
+            [("VIRTUAL_ENV", envdir.into_os_string()), ("PATH", path)];
+        run_xargs(
+            &hook_cmd(&hook.entry, &hook.args)?,
+            file_args,
+            hook.require_serial,
+            &envs,
+        )
+    }
+}
@@ -0,0 +1,15 @@
+use crate::hook::Hook;
+use crate::languages::{hook_cmd, run_xargs, Language};
+
+pub(crate) struct Script;
+
+impl Language for Script {
+    fn run_hook(&self, hook: &Hook, file_args: &[String]) -> anyhow::Result<(i32, Vec<u8>)> {
+        let mut cmd = hook_cmd(&hook.entry, &hook.args)?;
+        // scripts are relative to the root of the hook repository
+        if let Some(exe) = cmd.first_mut() {
+            *exe = hook.prefix.join(&exe).to_string_lossy().into();
+        }
+        run_xargs(&cmd, file_args, hook.require_serial, &[])
+    }
+}
@@ -0,0 +1,15 @@
+use crate::hook::Hook;
+use crate::languages::{hook_cmd, run_xargs, Language};
+
+pub(crate) struct System;
+
+impl Language for System {
+    fn run_hook(&self, hook: &Hook, file_args: &[String]) -> anyhow::Result<(i32, Vec<u8>)> {
+        run_xargs(
+            &hook_cmd(&hook.entry, &hook.args)?,
+            file_args,
+            hook.require_serial,
+            &[],
+        )
+    }
+}
@@ -3,16 +3,35 @@ use cfgv_derive::Cfgv;
 use clap::{Args, Parser, Subcommand, ValueEnum};
 use pre_commit_rs_derive::PreCommitEnv;
 use std::env;
+use std::fmt;
 use std::path;
+use std::process;
 
 mod clientlib;
+mod color;
 mod commands;
 mod env_ext;
 mod git;
+mod hook;
+mod identify;
+mod languages;
+mod output;
+mod process_ext;
+mod repository;
 mod staged_files_only;
 mod store;
+#[cfg(test)]
+mod testing;
+mod xargs;
 
-#[derive(Cfgv, Clone, Debug, ValueEnum)]
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ColorMode {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Cfgv, Clone, Copy, Debug, ValueEnum)]
 enum HookType {
     CommitMsg,
     PostCheckout,
@@ -26,21 +45,53 @@ enum HookType {
     PrepareCommitMsg,
 }
 
-#[derive(Cfgv, Clone, Debug, ValueEnum)]
+impl fmt::Display for HookType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+#[derive(Cfgv, Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
 enum Stage {
     CommitMsg,
     PostCheckout,
     PostCommit,
     PostMerge,
     PostRewrite,
+    #[cfgv_alias("commit")]
     PreCommit,
+    #[cfgv_alias("merge-commit")]
     PreMergeCommit,
+    #[cfgv_alias("push")]
     PrePush,
     PreRebase,
     PrepareCommitMsg,
     Manual,
 }
 
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+impl From<HookType> for Stage {
+    fn from(hook_type: HookType) -> Self {
+        match hook_type {
+            HookType::CommitMsg => Self::CommitMsg,
+            HookType::PostCheckout => Self::PostCheckout,
+            HookType::PostCommit => Self::PostCommit,
+            HookType::PostMerge => Self::PostMerge,
+            HookType::PostRewrite => Self::PostRewrite,
+            HookType::PreCommit => Self::PreCommit,
+            HookType::PreMergeCommit => Self::PreMergeCommit,
+            HookType::PrePush => Self::PrePush,
+            HookType::PreRebase => Self::PreRebase,
+            HookType::PrepareCommitMsg => Self::PrepareCommitMsg,
+        }
+    }
+}
+
 #[derive(Args, Debug)]
 struct Autoupdate {
     /// Update to the bleeding edge of `HEAD` instead of the latest tagged
@@ -108,6 +159,13 @@ struct Run {
     /// When hooks fail, run `git diff` directly afterward
     #[arg(long)]
     show_diff_on_failure: bool,
+    /// Stop running hooks after the first failure
+    #[arg(long)]
+    fail_fast: bool,
+    /// Run as a hosted CI runner would, also skipping the hooks listed in
+    /// the config's `ci: skip`
+    #[arg(long)]
+    ci: bool,
     /// (for usage with `--to-ref`) -- this option represents the original ref
     /// in a `from_ref...to_ref` diff expression.
     /// For `pre-push` hooks this represents the branch you are pushing to.
@@ -261,8 +319,10 @@ enum Commands {
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
-    #[arg(long, global = true)]
-    color: Option<String>,
+    /// Whether to use color in output
+    #[arg(value_enum, long, global = true, env = "PRE_COMMIT_COLOR")]
+    #[arg(default_value_t = ColorMode::Auto)]
+    color: ColorMode,
     #[arg(short = 'c', long, default_value = ".pre-commit-config.yaml")]
     config: String,
 }
@@ -311,13 +371,15 @@ fn _adjust_run(cmd: &mut Run, chdir: &Chdir) {
     }
 }
 
-fn main() -> anyhow::Result<()> {
+fn _main() -> anyhow::Result<i32> {
     let res = Cli::parse();
     let cmd = res.command.unwrap_or_else(|| {
         let argv = vec![env::args().next().unwrap(), "run".into()];
         Cli::parse_from(argv).command.unwrap()
     });
 
+    output::init(color::use_color(res.color));
+
     let store = store::Store::new()?;
 
     // these commands do not use the git repo
@@ -326,10 +388,10 @@ fn main() -> anyhow::Result<()> {
             return commands::clean::cmd(store);
         }
         Commands::Gc => {
-            panic!("not implemented");
+            return commands::gc::cmd(store);
         }
-        Commands::InitTemplatedir(_) => {
-            panic!("not implemented");
+        Commands::InitTemplatedir(cmd) => {
+            return commands::init_templatedir::cmd(&res.config, cmd);
         }
         Commands::SampleConfig => {
             return commands::sample_config::cmd();
@@ -349,18 +411,10 @@ fn main() -> anyhow::Result<()> {
     // You can check for the existence of subcommands, and if found use their
     // matches just as you would the top level cmd
     match cmd {
-        Commands::Autoupdate(_) => {
-            panic!("not implemented!");
-        }
-        Commands::Install(_) => {
-            panic!("not implemented!");
-        }
-        Commands::InstallHooks => {
-            panic!("not implemented!");
-        }
-        Commands::MigrateConfig => {
-            panic!("not implemented!");
-        }
+        Commands::Autoupdate(cmd) => commands::autoupdate::cmd(&config, cmd),
+        Commands::Install(cmd) => commands::install_uninstall::install(&config, &repo, &store, cmd),
+        Commands::InstallHooks => commands::install_uninstall::install_hooks(&config, &store),
+        Commands::MigrateConfig => commands::migrate_config::cmd(&config),
         Commands::Run(mut cmd) => {
             if let Some(chdir) = chdir {
                 _adjust_run(&mut cmd, &chdir);
@@ -372,14 +426,20 @@ fn main() -> anyhow::Result<()> {
                 cmd.repo = _chdir_path(&cmd.repo, &chdir);
                 _adjust_run(&mut cmd.run, &chdir);
             }
-            panic!("not implemented!");
-        }
-        Commands::Uninstall(_) => {
-            panic!("not implemented!");
-        }
-        Commands::HookImpl(_) => {
-            panic!("not implemented!");
+            commands::try_repo::cmd(repo, cmd)
         }
+        Commands::Uninstall(cmd) => commands::install_uninstall::uninstall(&config, &repo, cmd),
+        Commands::HookImpl(cmd) => commands::hook_impl::cmd(config, repo, store, cmd),
         _ => unreachable!(),
     }
 }
+
+fn main() -> process::ExitCode {
+    match _main() {
+        Ok(ret) => process::ExitCode::from(u8::try_from(ret).unwrap_or(1)),
+        Err(e) => {
+            output::error(format!("{e:#}"));
+            process::ExitCode::FAILURE
+        }
+    }
+}
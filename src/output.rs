@@ -0,0 +1,36 @@
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::color;
+
+static USE_COLOR: OnceLock<bool> = OnceLock::new();
+
+pub(crate) fn init(use_color: bool) {
+    USE_COLOR.set(use_color).unwrap();
+}
+
+pub(crate) fn use_color() -> bool {
+    *USE_COLOR.get().unwrap_or(&false)
+}
+
+fn _log<S: fmt::Display>(level: &str, level_color: &str, msg: S) {
+    let level = format!("[{level}]");
+    let level = if level_color.is_empty() {
+        level
+    } else {
+        color::format_color(&level, level_color, use_color())
+    };
+    println!("{level} {msg}");
+}
+
+pub(crate) fn info<S: fmt::Display>(msg: S) {
+    _log("INFO", "", msg);
+}
+
+pub(crate) fn warning<S: fmt::Display>(msg: S) {
+    _log("WARNING", color::YELLOW, msg);
+}
+
+pub(crate) fn error<S: fmt::Display>(msg: S) {
+    _log("ERROR", color::RED, msg);
+}
@@ -0,0 +1,17 @@
+use std::io;
+use std::io::Read;
+use std::process;
+
+/// run a command, capturing stdout and stderr interleaved into one buffer
+pub(crate) fn output_combined(mut cmd: process::Command) -> io::Result<(i32, Vec<u8>)> {
+    let (mut reader, writer) = io::pipe()?;
+    let mut child = cmd.stdout(writer.try_clone()?).stderr(writer).spawn()?;
+    // the `Command` holds the write end of the pipe -- drop it so reading
+    // finishes when the child exits
+    drop(cmd);
+
+    let mut out = Vec::new();
+    reader.read_to_end(&mut out)?;
+    let status = child.wait()?;
+    Ok((status.code().unwrap_or(255), out))
+}
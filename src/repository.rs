@@ -0,0 +1,194 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path;
+use std::thread;
+
+use crate::clientlib;
+use crate::clientlib::{Config, ManifestHook, Repo};
+use crate::hook::Hook;
+use crate::languages;
+use crate::output;
+use crate::store;
+
+pub(crate) const MANIFEST_FILE: &str = ".pre-commit-hooks.yaml";
+const STATE: &str = ".install_state_v2";
+
+fn _local_prefix(store: &store::Store, hook: &ManifestHook) -> anyhow::Result<path::PathBuf> {
+    if languages::get(&hook.language)?.environment_dir().is_none() {
+        // hooks without an environment work out of the current directory
+        Ok(path::PathBuf::from("."))
+    } else {
+        store.make_local(&hook.additional_dependencies)
+    }
+}
+
+fn _repository_hooks(repo: &Repo, store: &store::Store) -> anyhow::Result<Vec<Hook>> {
+    match repo {
+        Repo::Local(repo) => {
+            let mut ret = Vec::new();
+            for hook in &repo.hooks {
+                ret.push(Hook {
+                    src: repo.repo.clone(),
+                    prefix: _local_prefix(store, hook)?,
+                    hook: hook.clone(),
+                });
+            }
+            Ok(ret)
+        }
+        Repo::Meta(_) => {
+            anyhow::bail!("`repo: meta` is not implemented yet");
+        }
+        Repo::Remote(repo) => {
+            let path = store.clone(&repo.repo, &repo.rev, &[])?;
+            let manifest_path = path.join(MANIFEST_FILE);
+            let manifest = clientlib::load_manifest(&manifest_path.to_string_lossy())?;
+            let by_id: HashMap<&str, &ManifestHook> = manifest
+                .iter()
+                .map(|hook| (hook.id.as_str(), hook))
+                .collect();
+
+            let mut ret = Vec::new();
+            for hook in &repo.hooks {
+                let Some(manifest_hook) = by_id.get(hook.id.as_str()) else {
+                    anyhow::bail!(
+                        "`{}` is not present in repository {}.  Typo? Perhaps it is \
+                         introduced in a newer version?  Often `pre-commit autoupdate` \
+                         fixes this.",
+                        hook.id,
+                        repo.repo,
+                    );
+                };
+                let hook = hook.overlay_on(manifest_hook);
+                // hooks with `additional_dependencies` get their own checkout
+                let prefix = if hook.additional_dependencies.is_empty() {
+                    path.clone()
+                } else {
+                    store.clone(&repo.repo, &repo.rev, &hook.additional_dependencies)?
+                };
+                ret.push(Hook {
+                    src: repo.repo.clone(),
+                    prefix,
+                    hook,
+                });
+            }
+            Ok(ret)
+        }
+    }
+}
+
+pub(crate) fn all_hooks(config: &Config, store: &store::Store) -> anyhow::Result<Vec<Hook>> {
+    let mut ret = Vec::new();
+    for repo in &config.repos {
+        for mut hook in _repository_hooks(repo, store)? {
+            // config-wide defaults only apply where the hook sets nothing
+            if hook.language_version == "default" {
+                if let Some(version) = config.default_language_version.get(&hook.language) {
+                    hook.hook.language_version = version.clone();
+                }
+            }
+            if hook.stages.is_empty() {
+                hook.hook.stages = config.default_stages.clone();
+            }
+            ret.push(hook);
+        }
+    }
+    Ok(ret)
+}
+
+fn _hook_installed(hook: &Hook) -> anyhow::Result<bool> {
+    let lang = languages::get(&hook.language)?;
+    let Some(env_dir) = lang.environment_dir() else {
+        return Ok(true);
+    };
+    let venv = languages::environment_dir(&hook.prefix, env_dir, &hook.language_version);
+    Ok(venv.join(STATE).exists()
+        && lang
+            .health_check(&hook.prefix, &hook.language_version)
+            .is_none())
+}
+
+fn _hook_install(hook: &Hook) -> anyhow::Result<()> {
+    let lang = languages::get(&hook.language)?;
+    let env_dir = lang.environment_dir().unwrap();
+    let venv = languages::environment_dir(&hook.prefix, env_dir, &hook.language_version);
+
+    // There's potentially incomplete cleanup from previous runs
+    // Clean it up!
+    rm_rf::ensure_removed(&venv)?;
+
+    let installed = lang
+        .install_environment(
+            &hook.prefix,
+            &hook.language_version,
+            &hook.additional_dependencies,
+        )
+        .and_then(
+            |()| match lang.health_check(&hook.prefix, &hook.language_version) {
+                Some(health_error) => anyhow::bail!(
+                    "BUG: expected environment for {} to be healthy immediately after \
+                 install, please open an issue describing your environment\n\n\
+                 more info:\n\n{health_error}",
+                    hook.language,
+                ),
+                None => Ok(()),
+            },
+        );
+    if let Err(e) = installed {
+        rm_rf::ensure_removed(&venv)?;
+        return Err(e);
+    }
+
+    // Write our state to indicate we're installed
+    fs::write(venv.join(STATE), "")?;
+    Ok(())
+}
+
+fn _need_installed<'a>(hooks: &[&'a Hook]) -> anyhow::Result<Vec<&'a Hook>> {
+    let mut seen = HashSet::new();
+    let mut ret = Vec::new();
+    for hook in hooks {
+        if seen.insert(hook.install_key()) && !_hook_installed(hook)? {
+            ret.push(*hook);
+        }
+    }
+    Ok(ret)
+}
+
+pub(crate) fn install_hook_envs(hooks: &[&Hook], store: &store::Store) -> anyhow::Result<()> {
+    if _need_installed(hooks)?.is_empty() {
+        return Ok(());
+    }
+
+    let lock = store.exclusive_lock()?;
+    // Another process may have already completed this work
+    let need_installed = _need_installed(hooks)?;
+
+    // environments within a single repository are installed serially,
+    // separate repositories are installed in parallel
+    let mut by_prefix: Vec<(&path::Path, Vec<&Hook>)> = Vec::new();
+    for hook in need_installed {
+        output::info(format!("Installing environment for {}.", hook.src));
+        match by_prefix
+            .iter_mut()
+            .find(|(prefix, _)| *prefix == hook.prefix)
+        {
+            Some((_, group)) => group.push(hook),
+            None => by_prefix.push((&hook.prefix, vec![hook])),
+        }
+    }
+    output::info("Once installed this environment will be reused.");
+    output::info("This may take a few minutes...");
+
+    thread::scope(|s| {
+        let handles: Vec<_> = by_prefix
+            .iter()
+            .map(|(_, group)| s.spawn(|| group.iter().try_for_each(|hook| _hook_install(hook))))
+            .collect();
+        handles
+            .into_iter()
+            .try_for_each(|handle| handle.join().unwrap())
+    })?;
+
+    drop(lock);
+    Ok(())
+}
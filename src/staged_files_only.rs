@@ -7,6 +7,8 @@ use std::path;
 use std::process;
 use std::time;
 
+use crate::output;
+
 pub(crate) const SKIP_POST_CHECKOUT: &str = "_PRE_COMMIT_SKIP_POST_CHECKOUT";
 
 const EMPTY_BLOB: gix::ObjectId = gix::ObjectId::empty_blob(gix::hash::Kind::Sha1);
@@ -144,9 +146,8 @@ impl UnstagedCleared {
             let patch_p = p.as_ref().join(patch_filename);
             let patch = patch_p.to_string_lossy().into();
 
-            // TODO: logging / coloring
-            println!("Unstaged files detected.");
-            println!("Stashing unstaged files to {patch}");
+            output::warning("Unstaged files detected.");
+            output::info(format!("Stashing unstaged files to {patch}."));
 
             fs::create_dir_all(p)?;
             fs::write(&patch_p, diff.stdout)?;
@@ -166,14 +167,14 @@ impl Drop for UnstagedCleared {
         let apply_attempt = _git_apply(&self.patch);
         // TODO: specific error types?
         if apply_attempt.is_err() {
-            // TODO: logging / coloring
-            println!("Stashed changes conflicted with hook auto-fixes... Rolling back fixes...");
+            output::warning(
+                "Stashed changes conflicted with hook auto-fixes... Rolling back fixes...",
+            );
             _discard_changes().unwrap();
             _git_apply(&self.patch).unwrap();
         }
 
-        // TODO: logging / coloring
-        println!("Restored changes from {}.", self.patch);
+        output::info(format!("Restored changes from {}.", self.patch));
     }
 }
 
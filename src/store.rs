@@ -2,6 +2,16 @@ use std::fs;
 use std::path;
 
 use crate::env_ext;
+use crate::git;
+use crate::output;
+
+pub(crate) const LOCAL_REPO_VERSION: &str = "1";
+
+static LOCAL_SETUP_PY: &str = r"from setuptools import setup
+
+
+setup(name='pre-commit-placeholder-package', version='0.0.0', py_modules=[])
+";
 
 fn _store_dir_impl(
     pre_commit_home: Option<path::PathBuf>,
@@ -47,14 +57,14 @@ pub(crate) struct Store {
 }
 
 impl Store {
-    fn _exclusive_lock(&self) -> anyhow::Result<fslock::LockFile> {
+    pub(crate) fn exclusive_lock(&self) -> anyhow::Result<fslock::LockFile> {
         if self.readonly {
             anyhow::bail!("attempted a write on a readonly store");
         }
 
         let mut lock = fslock::LockFile::open(&self.directory.join(".lock"))?;
         if !lock.try_lock()? {
-            println!("[INFO] Locking pre-commit directory");
+            output::info("Locking pre-commit directory");
             lock.lock()?;
         }
         Ok(lock)
@@ -79,7 +89,7 @@ impl Store {
             fs::create_dir_all(&self.directory)?;
 
             {
-                let lock = self._exclusive_lock()?;
+                let lock = self.exclusive_lock()?;
                 // another process may have already completed this work
                 if self._db_path().exists() {
                     return Ok(());
@@ -111,7 +121,10 @@ impl Store {
     }
 
     pub(crate) fn new() -> anyhow::Result<Self> {
-        let directory = _store_dir()?;
+        Self::at(_store_dir()?)
+    }
+
+    pub(crate) fn at(directory: path::PathBuf) -> anyhow::Result<Self> {
         let readonly = _readonly(&directory);
         let ret = Store {
             directory,
@@ -121,16 +134,243 @@ impl Store {
         Ok(ret)
     }
 
+    pub(crate) fn connect(&self) -> anyhow::Result<rusqlite::Connection> {
+        let conn = rusqlite::Connection::open(self._db_path())?;
+        self._create_config_table(&conn)?;
+        Ok(conn)
+    }
+
+    pub(crate) fn db_repo_name(repo: &str, deps: &[String]) -> String {
+        if deps.is_empty() {
+            repo.into()
+        } else {
+            format!("{repo}:{}", deps.join(","))
+        }
+    }
+
     pub(crate) fn mark_config_used(&self, path: &str) -> anyhow::Result<()> {
         if self.readonly {
             return Ok(());
         }
         if let Ok(p) = fs::canonicalize(path) {
             let pstr = p.to_string_lossy();
-            let conn = rusqlite::Connection::open(self._db_path())?;
-            self._create_config_table(&conn)?;
+            let conn = self.connect()?;
             conn.execute("INSERT OR IGNORE INTO configs VALUES (?)", (pstr,))?;
         }
         Ok(())
     }
+
+    fn _get_result(&self, repo: &str, rev: &str) -> anyhow::Result<Option<path::PathBuf>> {
+        let conn = self.connect()?;
+        let mut stmt = conn.prepare("SELECT path FROM repos WHERE repo = ? AND ref = ?")?;
+        let mut rows = stmt.query((repo, rev))?;
+        match rows.next()? {
+            Some(row) => Ok(Some(path::PathBuf::from(row.get::<_, String>(0)?))),
+            None => Ok(None),
+        }
+    }
+
+    fn _new_repo<F: FnOnce(&path::Path) -> anyhow::Result<()>>(
+        &self,
+        repo: &str,
+        rev: &str,
+        deps: &[String],
+        make_strategy: F,
+    ) -> anyhow::Result<path::PathBuf> {
+        let repo = Self::db_repo_name(repo, deps);
+        let repo = repo.as_str();
+
+        // Check if we already exist
+        if let Some(ret) = self._get_result(repo, rev)? {
+            return Ok(ret);
+        }
+
+        let lock = self.exclusive_lock()?;
+        // another process may have already completed this work
+        if let Some(ret) = self._get_result(repo, rev)? {
+            return Ok(ret);
+        }
+
+        output::info(format!("Initializing environment for {repo}."));
+
+        // the directory is cleaned up on failure
+        let directory = tempfile::Builder::new()
+            .prefix("repo")
+            .tempdir_in(&self.directory)?;
+        make_strategy(directory.path())?;
+        let directory = directory.into_path();
+
+        // Update our db with the created repo
+        let conn = self.connect()?;
+        conn.execute(
+            "INSERT INTO repos (repo, ref, path) VALUES (?, ?, ?)",
+            (repo, rev, directory.to_string_lossy()),
+        )?;
+
+        drop(lock);
+        Ok(directory)
+    }
+
+    /// Perform a complete clone of a repository and its submodules
+    fn _complete_clone(directory: &path::Path, rev: &str) -> anyhow::Result<()> {
+        git::cmd_output(directory, &["fetch", "origin", "--tags"])?;
+        git::cmd_output(directory, &["checkout", rev])?;
+        git::cmd_output(directory, &["submodule", "update", "--init", "--recursive"])?;
+        Ok(())
+    }
+
+    /// Perform a shallow clone of a repository and its submodules
+    fn _shallow_clone(directory: &path::Path, rev: &str) -> anyhow::Result<()> {
+        let git_config = "protocol.version=2";
+        git::cmd_output(
+            directory,
+            &["-c", git_config, "fetch", "origin", rev, "--depth=1"],
+        )?;
+        git::cmd_output(directory, &["checkout", "FETCH_HEAD"])?;
+        git::cmd_output(
+            directory,
+            &[
+                "-c",
+                git_config,
+                "submodule",
+                "update",
+                "--init",
+                "--recursive",
+                "--depth=1",
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Clone the given url and checkout the specific rev.
+    pub(crate) fn clone(
+        &self,
+        repo: &str,
+        rev: &str,
+        deps: &[String],
+    ) -> anyhow::Result<path::PathBuf> {
+        self._new_repo(repo, rev, deps, |directory| {
+            git::init_repo(directory, repo)?;
+            // servers may refuse a shallow fetch of an arbitrary sha
+            if Self::_shallow_clone(directory, rev).is_err() {
+                Self::_complete_clone(directory, rev)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Create a placeholder repository for `repo: local` hooks which need
+    /// an environment to be installed.
+    pub(crate) fn make_local(&self, deps: &[String]) -> anyhow::Result<path::PathBuf> {
+        self._new_repo("local", LOCAL_REPO_VERSION, deps, |directory| {
+            fs::write(directory.join("setup.py"), LOCAL_SETUP_PY)?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository;
+    use crate::testing;
+
+    fn _rev_parse(repo: &str, rev: &str) -> String {
+        let out = git::cmd_output(repo, &["rev-parse", &format!("{rev}^{{commit}}")]).unwrap();
+        out.trim().into()
+    }
+
+    fn _head(checkout: &path::Path) -> String {
+        let out = git::cmd_output(checkout, &["rev-parse", "HEAD"]).unwrap();
+        out.trim().into()
+    }
+
+    fn _repos(store: &Store) -> Vec<(String, String, String)> {
+        let conn = store.connect().unwrap();
+        let mut stmt = conn.prepare("SELECT repo, ref, path FROM repos").unwrap();
+        stmt.query_map((), |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn clone_shallow() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let repo = testing::hooks_repo(tmpdir.path());
+        let store = Store::at(tmpdir.path().join("store")).unwrap();
+
+        let checkout = store.clone(&repo, "v1.0", &[]).unwrap();
+        assert!(checkout.starts_with(&store.directory));
+        assert!(checkout.join(repository::MANIFEST_FILE).exists());
+        assert!(checkout.join(".git/shallow").exists());
+        assert_eq!(_head(&checkout), _rev_parse(&repo, "v1.0"));
+
+        assert_eq!(
+            _repos(&store),
+            [(repo, "v1.0".into(), checkout.to_string_lossy().into())],
+        );
+    }
+
+    #[test]
+    fn clone_is_cached() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let repo = testing::hooks_repo(tmpdir.path());
+        let store = Store::at(tmpdir.path().join("store")).unwrap();
+
+        let checkout = store.clone(&repo, "v1.0", &[]).unwrap();
+        assert_eq!(store.clone(&repo, "v1.0", &[]).unwrap(), checkout);
+        assert_eq!(_repos(&store).len(), 1);
+
+        // other revisions and dependencies get their own checkout
+        let other = store.clone(&repo, "v2.0", &[]).unwrap();
+        assert_ne!(other, checkout);
+        let with_deps = store.clone(&repo, "v1.0", &["dep".into()]).unwrap();
+        assert_ne!(with_deps, checkout);
+        let names: Vec<String> = _repos(&store).into_iter().map(|row| row.0).collect();
+        assert!(names.contains(&format!("{repo}:dep")), "{names:?}");
+        assert_eq!(names.len(), 3);
+    }
+
+    #[test]
+    fn clone_falls_back_to_complete_clone() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let repo = testing::hooks_repo(tmpdir.path());
+        let store = Store::at(tmpdir.path().join("store")).unwrap();
+
+        // an abbreviated sha cannot be fetched directly
+        let sha = _rev_parse(&repo, "v1.0");
+        let checkout = store.clone(&repo, &sha[..7], &[]).unwrap();
+        assert!(!checkout.join(".git/shallow").exists());
+        assert_eq!(_head(&checkout), sha);
+        assert_eq!(_repos(&store)[0].1, &sha[..7]);
+    }
+
+    #[test]
+    fn clone_failure_is_not_recorded() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let repo = testing::hooks_repo(tmpdir.path());
+        let store = Store::at(tmpdir.path().join("store")).unwrap();
+
+        assert!(store.clone(&repo, "does-not-exist", &[]).is_err());
+        assert!(_repos(&store).is_empty());
+    }
+
+    #[test]
+    fn make_local() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let store = Store::at(tmpdir.path().join("store")).unwrap();
+
+        let checkout = store.make_local(&[]).unwrap();
+        assert!(checkout.join("setup.py").exists());
+        assert_eq!(store.make_local(&[]).unwrap(), checkout);
+        assert_eq!(
+            _repos(&store),
+            [(
+                "local".into(),
+                LOCAL_REPO_VERSION.into(),
+                checkout.to_string_lossy().into(),
+            )],
+        );
+    }
 }
@@ -0,0 +1,50 @@
+//! helpers shared by the unit tests
+use std::fs;
+use std::path;
+
+use crate::git;
+use crate::repository;
+
+pub(crate) const MANIFEST: &str = "\
+-   id: hook
+    name: hook
+    entry: echo
+    language: system
+";
+
+pub(crate) fn commit(repo: &path::Path, msg: &str) {
+    git::cmd_output(repo, &["add", "."]).unwrap();
+    git::cmd_output(
+        repo,
+        &[
+            "-c",
+            "user.name=pre-commit",
+            "-c",
+            "user.email=pre-commit@localhost",
+            "commit",
+            "--no-gpg-sign",
+            "--allow-empty",
+            "-qm",
+            msg,
+        ],
+    )
+    .unwrap();
+}
+
+/// a bare repository providing `MANIFEST` with commits tagged `v1.0` and
+/// `v2.0`, plus an untagged commit on top
+pub(crate) fn hooks_repo(dir: &path::Path) -> String {
+    let work = dir.join("work");
+    fs::create_dir(&work).unwrap();
+    git::cmd_output(&work, &["init", "--template=", "-q"]).unwrap();
+    fs::write(work.join(repository::MANIFEST_FILE), MANIFEST).unwrap();
+    commit(&work, "v1");
+    git::cmd_output(&work, &["tag", "v1.0"]).unwrap();
+    commit(&work, "v2");
+    git::cmd_output(&work, &["tag", "v2.0"]).unwrap();
+    commit(&work, "untagged");
+
+    let bare = dir.join("hooks.git");
+    git::cmd_output(dir, &["clone", "-q", "--bare", "work", "hooks.git"]).unwrap();
+    bare.to_string_lossy().into_owned()
+}
@@ -0,0 +1,229 @@
+use std::env;
+use std::ffi;
+use std::io;
+use std::process;
+use std::thread;
+
+use crate::process_ext;
+
+pub(crate) fn cpu_count() -> usize {
+    // this takes into account affinity and cgroup limits where supported
+    thread::available_parallelism().map_or(1, usize::from)
+}
+
+fn _environ_size() -> usize {
+    env::vars_os()
+        // a pointer in `envp` plus the c string `k=v`
+        .map(|(k, v)| 8 + k.len() + v.len() + 2)
+        .sum()
+}
+
+#[cfg(unix)]
+fn _get_platform_max_length() -> usize {
+    // SAFETY: `sysconf` has no preconditions
+    let arg_max = unsafe { libc::sysconf(libc::_SC_ARG_MAX) };
+    let maximum = usize::try_from(arg_max)
+        .unwrap_or(0)
+        .saturating_sub(2048)
+        .saturating_sub(_environ_size());
+    maximum.clamp(1 << 12, 1 << 17)
+}
+
+#[cfg(windows)]
+fn _get_platform_max_length() -> usize {
+    // UNICODE_STRING max - headroom
+    (1 << 15) - 2048
+}
+
+#[cfg(not(any(unix, windows)))]
+fn _get_platform_max_length() -> usize {
+    // posix minimum
+    1 << 12
+}
+
+fn _command_length<S: AsRef<str>>(cmd: &[S]) -> usize {
+    let len: usize = cmd
+        .iter()
+        .map(|part| {
+            let part = part.as_ref();
+            // win32 counts characters rather than bytes
+            if cfg!(windows) {
+                part.encode_utf16().count()
+            } else {
+                part.len()
+            }
+        })
+        .sum();
+    len + cmd.len().saturating_sub(1)
+}
+
+/// split `varargs` into invocations of `cmd` which each fit within
+/// `max_length`, aiming for at least `target_concurrency` invocations.
+pub(crate) fn partition(
+    cmd: &[String],
+    varargs: &[String],
+    target_concurrency: usize,
+    max_length: usize,
+) -> anyhow::Result<Vec<Vec<String>>> {
+    // Generally, we try to partition evenly into at least
+    // `target_concurrency` partitions, but we don't want a bunch of tiny
+    // partitions.
+    let max_args = varargs.len().div_ceil(target_concurrency.max(1)).max(4);
+
+    let cmd_length = _command_length(cmd) + 1;
+    let mut ret = Vec::new();
+    let mut ret_cmd: Vec<String> = Vec::new();
+    let mut total_length = cmd_length;
+    for arg in varargs {
+        let arg_length = _command_length(&[arg]) + 1;
+        if !ret_cmd.is_empty()
+            && (total_length + arg_length > max_length || ret_cmd.len() >= max_args)
+        {
+            // We've exceeded the length, yield a command
+            ret.push([cmd, &ret_cmd].concat());
+            ret_cmd.clear();
+            total_length = cmd_length;
+        }
+        if total_length + arg_length > max_length {
+            anyhow::bail!("Argument is too long: {arg}");
+        }
+        ret_cmd.push(arg.clone());
+        total_length += arg_length;
+    }
+    ret.push([cmd, &ret_cmd].concat());
+
+    Ok(ret)
+}
+
+fn _run_partition(
+    run_cmd: &[String],
+    envs: &[(&str, ffi::OsString)],
+) -> io::Result<(i32, Vec<u8>)> {
+    let mut command = process::Command::new(&run_cmd[0]);
+    command.args(&run_cmd[1..]).envs(envs.iter().cloned());
+    process_ext::output_combined(command)
+}
+
+/// A simplified implementation of xargs.
+///
+/// partitions run on up to `target_concurrency` threads, output is combined
+/// in partition order so it does not depend on scheduling.
+pub(crate) fn xargs(
+    cmd: &[String],
+    varargs: &[String],
+    target_concurrency: usize,
+    envs: &[(&str, ffi::OsString)],
+) -> anyhow::Result<(i32, Vec<u8>)> {
+    let Some(exe) = cmd.first() else {
+        anyhow::bail!("hook has an empty `entry`");
+    };
+
+    let partitions = partition(cmd, varargs, target_concurrency, _get_platform_max_length())?;
+
+    let threads = partitions.len().min(target_concurrency).max(1);
+    let results: Vec<io::Result<(i32, Vec<u8>)>> = if threads == 1 {
+        partitions
+            .iter()
+            .map(|run_cmd| _run_partition(run_cmd, envs))
+            .collect()
+    } else {
+        // partitions are dealt round-robin to a fixed pool of threads
+        let mut results = thread::scope(|s| {
+            let handles: Vec<_> = (0..threads)
+                .map(|t| {
+                    let partitions = &partitions;
+                    s.spawn(move || {
+                        partitions
+                            .iter()
+                            .enumerate()
+                            .skip(t)
+                            .step_by(threads)
+                            .map(|(i, run_cmd)| (i, _run_partition(run_cmd, envs)))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        results.sort_by_key(|(i, _)| *i);
+        results.into_iter().map(|(_, result)| result).collect()
+    };
+
+    let mut retcode: i32 = 0;
+    let mut stdout = Vec::new();
+    for result in results {
+        let (proc_retcode, proc_out) = match result {
+            Ok(ret) => ret,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Ok((1, format!("Executable `{exe}` not found").into_bytes()));
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if proc_retcode.abs() > retcode.abs() {
+            retcode = proc_retcode;
+        }
+        stdout.extend(proc_out);
+    }
+
+    Ok((retcode, stdout))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn _strs(parts: &[&str]) -> Vec<String> {
+        parts.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn partition_trivial() {
+        let ret = partition(&_strs(&["cmd"]), &[], 1, 4096).unwrap();
+        assert_eq!(ret, vec![_strs(&["cmd"])]);
+    }
+
+    #[test]
+    fn partition_simple() {
+        let ret = partition(&_strs(&["cmd", "foo"]), &_strs(&["file"]), 1, 4096).unwrap();
+        assert_eq!(ret, vec![_strs(&["cmd", "foo", "file"])]);
+    }
+
+    #[test]
+    fn partition_limits() {
+        let varargs = _strs(&[".....", "....", ".....", "......", "......."]);
+        let ret = partition(&_strs(&["ninechars"]), &varargs, 1, 21).unwrap();
+        assert_eq!(
+            ret,
+            vec![
+                _strs(&["ninechars", ".....", "...."]),
+                _strs(&["ninechars", "....."]),
+                _strs(&["ninechars", "......"]),
+                _strs(&["ninechars", "......."]),
+            ],
+        );
+    }
+
+    #[test]
+    fn partition_target_concurrency() {
+        let ret = partition(&_strs(&["foo"]), &_strs(&["A"; 22]), 4, 50).unwrap();
+        let lens: Vec<usize> = ret.iter().map(Vec::len).collect();
+        assert_eq!(lens, vec![7, 7, 7, 5]);
+        assert!(ret.iter().all(|run_cmd| run_cmd[0] == "foo"));
+    }
+
+    #[test]
+    fn partition_concurrency_minimum_partition_size() {
+        // exactly 4 args per partition are fine even with more jobs
+        let ret = partition(&_strs(&["foo"]), &_strs(&["A"; 3]), 4, 50).unwrap();
+        assert_eq!(ret, vec![_strs(&["foo", "A", "A", "A"])]);
+    }
+
+    #[test]
+    fn partition_argument_too_long() {
+        let ret = partition(&_strs(&["aaaaa"]), &_strs(&["aaaaa"]), 1, 10);
+        assert_eq!(ret.unwrap_err().to_string(), "Argument is too long: aaaaa",);
+    }
+}
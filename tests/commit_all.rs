@@ -0,0 +1,41 @@
+use std::fs;
+
+mod common;
+
+const CONFIG: &str = "\
+repos:
+-   repo: local
+    hooks:
+    -   id: always-fails
+        name: always fails
+        entry: 'false'
+        language: system
+";
+
+#[test]
+fn commit_all_runs_hooks_on_the_temporary_index() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let store = tmpdir.path().join("store");
+    let repo = common::init_repo(tmpdir.path(), CONFIG, &[("f.txt", "hello\n")]);
+
+    let install = common::pre_commit(&repo, &store)
+        .arg("install")
+        .output()
+        .unwrap();
+    assert!(install.status.success(), "{install:?}");
+
+    // the change is only staged into the temporary index `git commit -a` uses
+    fs::write(repo.join("f.txt"), "hello world\n").unwrap();
+    let commit = common::git(&repo)
+        .args(["commit", "-am", "x"])
+        .env("PRE_COMMIT_HOME", &store)
+        .output()
+        .unwrap();
+    // git sends the output of hooks to stderr
+    let out = common::stderr(&commit);
+
+    assert!(!commit.status.success(), "{out}");
+    assert!(out.contains("always fails"), "{out}");
+    assert!(out.contains("Failed"), "{out}");
+    assert!(!out.contains("no files to check"), "{out}");
+}
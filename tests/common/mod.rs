@@ -0,0 +1,70 @@
+// each test binary uses a different subset of these
+#![allow(dead_code)]
+
+use std::fs;
+use std::path;
+use std::process;
+
+pub const CONFIG_FILE: &str = ".pre-commit-config.yaml";
+
+fn _command(program: &str, cwd: &path::Path) -> process::Command {
+    let mut cmd = process::Command::new(program);
+    cmd.current_dir(cwd)
+        .env_remove("GIT_INDEX_FILE")
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("PRE_COMMIT_COLOR", "never");
+    cmd
+}
+
+/// `git` with an identity so commits work without any user config
+pub fn git(cwd: &path::Path) -> process::Command {
+    let mut cmd = _command("git", cwd);
+    cmd.args([
+        "-c",
+        "user.name=pre-commit",
+        "-c",
+        "user.email=pre-commit@localhost",
+        "-c",
+        "commit.gpgsign=false",
+    ]);
+    cmd
+}
+
+/// run `git` and return its stdout, panicking on failure
+pub fn git_ok(cwd: &path::Path, args: &[&str]) -> String {
+    let output = git(cwd).args(args).output().unwrap();
+    assert!(output.status.success(), "git {args:?}: {output:?}");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// this crate's binary with its store at `store`
+pub fn pre_commit(cwd: &path::Path, store: &path::Path) -> process::Command {
+    let mut cmd = _command(env!("CARGO_BIN_EXE_pre-commit-rs"), cwd);
+    cmd.env("PRE_COMMIT_HOME", store);
+    cmd
+}
+
+/// a repository at `dir/repo` with `config` and `files` committed
+pub fn init_repo(dir: &path::Path, config: &str, files: &[(&str, &str)]) -> path::PathBuf {
+    let repo = dir.join("repo");
+    fs::create_dir(&repo).unwrap();
+    git_ok(
+        &repo,
+        &["init", "-q", "--template=", "--initial-branch=main"],
+    );
+    fs::write(repo.join(CONFIG_FILE), config).unwrap();
+    for (name, contents) in files {
+        fs::write(repo.join(name), contents).unwrap();
+    }
+    git_ok(&repo, &["add", "."]);
+    git_ok(&repo, &["commit", "-qm", "init"]);
+    repo
+}
+
+pub fn stdout(output: &process::Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+pub fn stderr(output: &process::Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
@@ -0,0 +1,134 @@
+use std::fs;
+use std::io::Write;
+use std::path;
+use std::process;
+
+mod common;
+
+const CONFIG: &str = "\
+repos:
+-   repo: local
+    hooks:
+    -   id: print-files
+        name: print files
+        entry: echo
+        language: system
+        verbose: true
+        stages: [pre-push]
+";
+
+const Z40: &str = "0000000000000000000000000000000000000000";
+
+struct Repos {
+    _tmpdir: tempfile::TempDir,
+    store: path::PathBuf,
+    repo: path::PathBuf,
+    remote: String,
+}
+
+fn _repos() -> Repos {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let store = tmpdir.path().join("store");
+    let repo = common::init_repo(tmpdir.path(), CONFIG, &[("a.txt", "a\n")]);
+    let remote = tmpdir.path().join("remote.git");
+    common::git_ok(tmpdir.path(), &["init", "-q", "--bare", "remote.git"]);
+    let remote = remote.to_string_lossy().into_owned();
+    common::git_ok(&repo, &["remote", "add", "origin", &remote]);
+    Repos {
+        _tmpdir: tmpdir,
+        store,
+        repo,
+        remote,
+    }
+}
+
+fn _commit_file(repo: &path::Path, name: &str) -> String {
+    fs::write(repo.join(name), "contents\n").unwrap();
+    common::git_ok(repo, &["add", name]);
+    common::git_ok(repo, &["commit", "-qm", name]);
+    common::git_ok(repo, &["rev-parse", "HEAD"]).trim().into()
+}
+
+/// run the pre-push hook as git would, returning the files it checked
+fn _pre_push(repos: &Repos, stdin: &str) -> Option<String> {
+    let mut child = common::pre_commit(&repos.repo, &repos.store)
+        .args(["hook-impl", "--hook-type=pre-push", "--hook-dir"])
+        .arg(repos.repo.join(".git/hooks"))
+        .args(["--", "origin", &repos.remote])
+        .stdin(process::Stdio::piped())
+        .stdout(process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let out = common::stdout(&output);
+    assert!(output.status.success(), "{out}");
+
+    if !out.contains("print files") {
+        return None;
+    }
+    // the verbose output of `echo`, after the hook's details.  the order
+    // of the filenames is not significant
+    let (_, files) = out.split_once("\n\n").unwrap();
+    let mut files: Vec<&str> = files.split_whitespace().collect();
+    files.sort();
+    Some(files.join(" "))
+}
+
+#[test]
+fn pre_push_new_repository_checks_all_files() {
+    let repos = _repos();
+    let head = common::git_ok(&repos.repo, &["rev-parse", "HEAD"]);
+    let stdin = format!("refs/heads/main {} refs/heads/main {Z40}\n", head.trim());
+
+    let files = _pre_push(&repos, &stdin).unwrap();
+    assert_eq!(files, ".pre-commit-config.yaml a.txt");
+}
+
+#[test]
+fn pre_push_existing_branch_checks_new_commits() {
+    let repos = _repos();
+    common::git_ok(&repos.repo, &["push", "-q", "origin", "main"]);
+    let remote_sha = common::git_ok(&repos.repo, &["rev-parse", "HEAD"]);
+    _commit_file(&repos.repo, "b.txt");
+    let head = _commit_file(&repos.repo, "c.txt");
+
+    let stdin = format!(
+        "refs/heads/main {head} refs/heads/main {}\n",
+        remote_sha.trim(),
+    );
+    assert_eq!(_pre_push(&repos, &stdin).unwrap(), "b.txt c.txt");
+}
+
+#[test]
+fn pre_push_new_branch_checks_commits_not_on_the_remote() {
+    let repos = _repos();
+    _commit_file(&repos.repo, "b.txt");
+    common::git_ok(&repos.repo, &["push", "-q", "origin", "main"]);
+    common::git_ok(&repos.repo, &["checkout", "-q", "-b", "feature"]);
+    let head = _commit_file(&repos.repo, "c.txt");
+
+    let stdin = format!("refs/heads/feature {head} refs/heads/feature {Z40}\n");
+    assert_eq!(_pre_push(&repos, &stdin).unwrap(), "c.txt");
+
+    // a new branch of commits the remote already has
+    let main = common::git_ok(&repos.repo, &["rev-parse", "main"]);
+    let stdin = format!("refs/heads/main2 {} refs/heads/main2 {Z40}\n", main.trim());
+    assert_eq!(_pre_push(&repos, &stdin), None);
+}
+
+#[test]
+fn pre_push_delete_runs_nothing() {
+    let repos = _repos();
+    common::git_ok(&repos.repo, &["push", "-q", "origin", "main"]);
+    let remote_sha = common::git_ok(&repos.repo, &["rev-parse", "HEAD"]);
+
+    let stdin = format!("(delete) {Z40} refs/heads/main {}\n", remote_sha.trim());
+    assert_eq!(_pre_push(&repos, &stdin), None);
+    assert_eq!(_pre_push(&repos, ""), None);
+}
@@ -0,0 +1,204 @@
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path;
+
+mod common;
+
+const CONFIG: &str = "\
+repos:
+-   repo: local
+    hooks:
+    -   id: marker
+        name: marker
+        entry: touch ran-pre-commit
+        language: system
+        pass_filenames: false
+        always_run: true
+";
+
+const LEGACY: &str = "#!/bin/sh\ntouch ran-legacy\n";
+
+fn _write_hook(path: &path::Path, contents: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+fn _commit(repo: &path::Path, store: &path::Path) -> bool {
+    for marker in ["ran-pre-commit", "ran-legacy"] {
+        let _ = fs::remove_file(repo.join(marker));
+    }
+    common::git(repo)
+        .args(["commit", "-q", "--allow-empty", "-m", "x"])
+        .env("PRE_COMMIT_HOME", store)
+        .output()
+        .unwrap()
+        .status
+        .success()
+}
+
+fn _install(repo: &path::Path, store: &path::Path) {
+    let install = common::pre_commit(repo, store)
+        .arg("install")
+        .output()
+        .unwrap();
+    assert!(install.status.success(), "{install:?}");
+}
+
+#[test]
+fn install_hook_script() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let store = tmpdir.path().join("store");
+    let repo = common::init_repo(tmpdir.path(), CONFIG, &[]);
+    let hook = repo.join(".git/hooks/pre-commit");
+
+    let install = common::pre_commit(&repo, &store)
+        .arg("install")
+        .output()
+        .unwrap();
+    assert!(install.status.success(), "{install:?}");
+    let out = common::stdout(&install);
+    assert_eq!(out, "pre-commit installed at .git/hooks/pre-commit\n");
+    let contents = fs::read_to_string(&hook).unwrap();
+    assert!(
+        contents.contains("# File generated by pre-commit"),
+        "{contents}"
+    );
+    assert!(hook.metadata().unwrap().permissions().mode() & 0o111 != 0);
+
+    assert!(_commit(&repo, &store));
+    assert!(repo.join("ran-pre-commit").exists());
+}
+
+#[test]
+fn install_migrates_legacy_hook() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let store = tmpdir.path().join("store");
+    let repo = common::init_repo(tmpdir.path(), CONFIG, &[]);
+    let legacy = repo.join(".git/hooks/pre-commit.legacy");
+    _write_hook(&repo.join(".git/hooks/pre-commit"), LEGACY);
+
+    let install = common::pre_commit(&repo, &store)
+        .arg("install")
+        .output()
+        .unwrap();
+    assert!(install.status.success(), "{install:?}");
+    let out = common::stdout(&install);
+    assert!(out.starts_with("Running in migration mode"), "{out}");
+    assert_eq!(fs::read_to_string(&legacy).unwrap(), LEGACY);
+
+    // both the previous hook and pre-commit run
+    assert!(_commit(&repo, &store));
+    assert!(repo.join("ran-legacy").exists());
+    assert!(repo.join("ran-pre-commit").exists());
+
+    // installing again keeps the legacy hook rather than moving our own
+    let install = common::pre_commit(&repo, &store)
+        .arg("install")
+        .output()
+        .unwrap();
+    assert!(install.status.success(), "{install:?}");
+    assert_eq!(fs::read_to_string(&legacy).unwrap(), LEGACY);
+}
+
+#[test]
+fn install_overwrite_removes_legacy_hook() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let store = tmpdir.path().join("store");
+    let repo = common::init_repo(tmpdir.path(), CONFIG, &[]);
+    _write_hook(&repo.join(".git/hooks/pre-commit"), LEGACY);
+
+    let install = common::pre_commit(&repo, &store)
+        .args(["install", "-f"])
+        .output()
+        .unwrap();
+    assert!(install.status.success(), "{install:?}");
+    assert!(!repo.join(".git/hooks/pre-commit.legacy").exists());
+
+    assert!(_commit(&repo, &store));
+    assert!(!repo.join("ran-legacy").exists());
+    assert!(repo.join("ran-pre-commit").exists());
+}
+
+#[test]
+fn install_refuses_core_hooks_path() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let store = tmpdir.path().join("store");
+    let repo = common::init_repo(tmpdir.path(), CONFIG, &[]);
+    common::git_ok(&repo, &["config", "core.hooksPath", "my-hooks"]);
+
+    let install = common::pre_commit(&repo, &store)
+        .arg("install")
+        .output()
+        .unwrap();
+    assert_eq!(install.status.code(), Some(1), "{install:?}");
+    let out = common::stdout(&install);
+    assert!(
+        out.contains("[ERROR] Cowardly refusing to install hooks with `core.hooksPath` set."),
+        "{out}",
+    );
+    assert!(!repo.join(".git/hooks/pre-commit").exists());
+    assert!(!repo.join("my-hooks/pre-commit").exists());
+}
+
+#[test]
+fn uninstall_removes_hook() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let store = tmpdir.path().join("store");
+    let repo = common::init_repo(tmpdir.path(), CONFIG, &[]);
+    _install(&repo, &store);
+
+    let uninstall = common::pre_commit(&repo, &store)
+        .arg("uninstall")
+        .output()
+        .unwrap();
+    assert!(uninstall.status.success(), "{uninstall:?}");
+    assert_eq!(common::stdout(&uninstall), "pre-commit uninstalled\n");
+    assert!(!repo.join(".git/hooks/pre-commit").exists());
+
+    assert!(_commit(&repo, &store));
+    assert!(!repo.join("ran-pre-commit").exists());
+}
+
+#[test]
+fn uninstall_restores_legacy_hook() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let store = tmpdir.path().join("store");
+    let repo = common::init_repo(tmpdir.path(), CONFIG, &[]);
+    let hook = repo.join(".git/hooks/pre-commit");
+    let legacy = repo.join(".git/hooks/pre-commit.legacy");
+    _write_hook(&hook, LEGACY);
+    _install(&repo, &store);
+    assert!(legacy.exists());
+
+    let uninstall = common::pre_commit(&repo, &store)
+        .arg("uninstall")
+        .output()
+        .unwrap();
+    assert!(uninstall.status.success(), "{uninstall:?}");
+    let out = common::stdout(&uninstall);
+    assert!(out.contains("Restored previous hooks to"), "{out}");
+    assert_eq!(fs::read_to_string(&hook).unwrap(), LEGACY);
+    assert!(!legacy.exists());
+
+    assert!(_commit(&repo, &store));
+    assert!(repo.join("ran-legacy").exists());
+    assert!(!repo.join("ran-pre-commit").exists());
+}
+
+#[test]
+fn uninstall_leaves_other_hooks() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let store = tmpdir.path().join("store");
+    let repo = common::init_repo(tmpdir.path(), CONFIG, &[]);
+    let hook = repo.join(".git/hooks/pre-commit");
+    _write_hook(&hook, LEGACY);
+
+    let uninstall = common::pre_commit(&repo, &store)
+        .arg("uninstall")
+        .output()
+        .unwrap();
+    assert!(uninstall.status.success(), "{uninstall:?}");
+    assert_eq!(common::stdout(&uninstall), "");
+    assert_eq!(fs::read_to_string(&hook).unwrap(), LEGACY);
+}
@@ -0,0 +1,96 @@
+use std::fs;
+
+mod common;
+
+const CONFIG: &str = "\
+repos:
+-   repo: local
+    hooks:
+    -   id: passes
+        name: passes
+        entry: 'true'
+        language: system
+    -   id: rewrite
+        name: rewrite
+        entry: sed -i s/hello/goodbye/
+        language: system
+        files: ^f\\.txt$
+";
+
+#[test]
+fn hook_modifying_files_fails() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let store = tmpdir.path().join("store");
+    let repo = common::init_repo(tmpdir.path(), CONFIG, &[("f.txt", "hello\n")]);
+
+    let run = common::pre_commit(&repo, &store)
+        .args(["run", "--all-files"])
+        .output()
+        .unwrap();
+    let out = common::stdout(&run);
+
+    assert_eq!(run.status.code(), Some(1), "{out}");
+    assert!(out.contains("passes"), "{out}");
+    assert!(out.contains("- hook id: rewrite\n"), "{out}");
+    assert!(
+        out.contains("- files were modified by this hook\n"),
+        "{out}"
+    );
+    assert!(!out.contains("- exit code"), "{out}");
+    assert!(!out.contains("All changes made by hooks:"), "{out}");
+    assert_eq!(fs::read_to_string(repo.join("f.txt")).unwrap(), "goodbye\n");
+
+    // nothing left to modify
+    let run = common::pre_commit(&repo, &store)
+        .args(["run", "--all-files"])
+        .output()
+        .unwrap();
+    assert!(run.status.success(), "{run:?}");
+}
+
+#[test]
+fn show_diff_on_failure() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let store = tmpdir.path().join("store");
+    let repo = common::init_repo(tmpdir.path(), CONFIG, &[("f.txt", "hello\n")]);
+
+    let run = common::pre_commit(&repo, &store)
+        .args(["run", "--all-files", "--show-diff-on-failure"])
+        .output()
+        .unwrap();
+    let out = common::stdout(&run);
+
+    assert_eq!(run.status.code(), Some(1), "{out}");
+    assert!(
+        out.contains("- files were modified by this hook\n"),
+        "{out}"
+    );
+    assert!(
+        out.contains("reproduce locally with: `pre-commit run --all-files`"),
+        "{out}"
+    );
+    let (_, diff) = out.split_once("All changes made by hooks:\n").unwrap();
+    assert!(diff.contains("diff --git a/f.txt b/f.txt\n"), "{out}");
+    assert!(diff.contains("\n-hello\n+goodbye\n"), "{out}");
+}
+
+#[test]
+fn modifications_before_the_run_do_not_fail_hooks() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let store = tmpdir.path().join("store");
+    let repo = common::init_repo(
+        tmpdir.path(),
+        CONFIG,
+        &[("f.txt", "hello\n"), ("g.txt", "unrelated\n")],
+    );
+    fs::write(repo.join("g.txt"), "changed by the user\n").unwrap();
+    fs::write(repo.join("f.txt"), "already goodbye\n").unwrap();
+
+    let run = common::pre_commit(&repo, &store)
+        .args(["run", "--all-files", "--show-diff-on-failure"])
+        .output()
+        .unwrap();
+    let out = common::stdout(&run);
+    assert!(run.status.success(), "{out}");
+    assert!(!out.contains("All changes made by hooks:"), "{out}");
+}